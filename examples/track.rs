@@ -53,10 +53,46 @@ impl<'a> BPSetter<'a> for BreakPoints {
 type CallbackRet = Result<(), Box<dyn std::error::Error>>;
 type BreakCallback = Box<dyn Fn(&mut CallBackInfo) -> CallbackRet>;
 
+// The program counters are file addresses in the module holding the allocator, as it is laid out
+// at its preferred base. They are relocated to wherever the module actually got loaded.
 const pc_allocation_entry: u64 = 0x6ff6cd50; // program counter for this breakpoint.
+const pc_allocation_return: u64 = 0x6ff6cd8a;
+
+/// Load addresses of the breakpoints in the running process.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Pcs {
+    allocation_entry: u64,
+    allocation_return: u64,
+}
+
+/// Relocate a file address of the module to its load address in the process.
+fn relocate(
+    target: &mut UniquePtr<bindings::SBTarget>,
+    module: &str,
+    file_address: u64,
+) -> Result<u64, BError> {
+    let m = target
+        .find_module_by_name(module)
+        .ok_or_else(|| format!("module {module} is not loaded"))?;
+    let base = m
+        .load_base(&*target)
+        .ok_or_else(|| format!("module {module} has no load address"))?;
+    let preferred = m
+        .get_object_file_header_address()
+        .get_file_address()
+        .ok_or_else(|| format!("module {module} has no image base"))?;
+    let offset = file_address.checked_sub(preferred).ok_or_else(|| {
+        format!("address {file_address:#x} is below the image base {preferred:#x} of {module}")
+    })?;
+    let address = base.checked_add(offset).ok_or_else(|| {
+        format!("offset {offset:#x} from the load address {base:#x} of {module} overflows")
+    })?;
+    Ok(address)
+}
+
 pub fn break_allocation_entry(info: &mut CallBackInfo) -> CallbackRet {
     // Ensure we capture the return, enable that breakpoint.
-    info.bp.set(pc_allocation_return).set(BP::Enabled);
+    info.bp.set(info.pcs.allocation_return).set(BP::Enabled);
 
     // https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBFrame.h
     let mut sp_mut = info.sp.borrow_mut();
@@ -100,10 +136,9 @@ pub fn break_allocation_entry(info: &mut CallBackInfo) -> CallbackRet {
     Ok(())
 }
 
-const pc_allocation_return: u64 = 0x6ff6cd8a;
 pub fn break_allocation_return(info: &mut CallBackInfo) -> CallbackRet {
     // Disable this breakpoint, now that we have caught the return
-    info.bp.set(pc_allocation_return).set(BP::Disabled);
+    info.bp.set(info.pcs.allocation_return).set(BP::Disabled);
 
     let mut sp_mut = info.sp.borrow_mut();
     let mut thread = sp_mut.process().thread(0);
@@ -151,6 +186,7 @@ type SharedProcess = Rc<RefCell<TargetProcess>>;
 pub struct CallBackInfo<'a> {
     sp: &'a SharedProcess,
     bp: &'a mut BreakPoints,
+    pcs: &'a Pcs,
     data: &'a mut Data,
 }
 
@@ -176,6 +212,7 @@ struct ProgramTracker {
     tp: SharedProcess,
     bp: RefCell<BreakPoints>,
     callbacks: std::collections::HashMap<u64, BreakCallback>,
+    pcs: Pcs,
     data: Data,
 }
 
@@ -186,6 +223,7 @@ impl ProgramTracker {
             tp: Rc::new(RefCell::new(tp)),
             bp: RefCell::new(std::collections::HashMap::new()),
             callbacks: std::collections::HashMap::new(),
+            pcs: Default::default(),
             data: Default::default(),
         }
     }
@@ -205,22 +243,33 @@ impl ProgramTracker {
         self.callbacks.insert(address, cb);
     }
 
-    /// Method to enable the appropriate breakpoints for tracking allocations.
-    pub fn register_bp_track_allocations(&mut self) {
+    /// Method to enable the appropriate breakpoints for tracking allocations, the module holding
+    /// the allocator must be loaded.
+    pub fn register_bp_track_allocations(&mut self, module: &str) -> Result<(), BError> {
+        {
+            let mut tp = self.tp.borrow_mut();
+            let target = tp.target();
+            self.pcs = Pcs {
+                allocation_entry: relocate(target, module, pc_allocation_entry)?,
+                allocation_return: relocate(target, module, pc_allocation_return)?,
+            };
+        }
+
         self.break_instruction(
-            pc_allocation_entry,
+            self.pcs.allocation_entry,
             Box::new(|z: _| break_allocation_entry(z)),
         );
         self.bp
             .borrow_mut()
-            .set(pc_allocation_entry)
+            .set(self.pcs.allocation_entry)
             .set(BP::Enabled);
 
         self.break_instruction(
-            pc_allocation_return,
+            self.pcs.allocation_return,
             Box::new(|z: _| break_allocation_return(z)),
         );
-        // self.bp.borrow_mut().set(pc_allocation_return).set(BP::OneShot);
+        // self.bp.borrow_mut().set(self.pcs.allocation_return).set(BP::OneShot);
+        Ok(())
     }
 
     /// Go into a loop, starting the program and waiting for stop events, calling the appropriate
@@ -228,7 +277,10 @@ impl ProgramTracker {
     pub fn go(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.tp.borrow_mut().start()?;
         loop {
-            let event = self.tp.borrow_mut().wait_for_event();
+            let event = self
+                .tp
+                .borrow_mut()
+                .wait_for_event(std::time::Duration::from_secs(10));
             if let Some(_event) = event {
                 // println!("{:?}", z);
                 // If we stopped, do things.
@@ -247,6 +299,7 @@ impl ProgramTracker {
                 let mut cb_info = CallBackInfo {
                     sp: &self.tp,
                     bp: &mut self.bp.borrow_mut(),
+                    pcs: &self.pcs,
                     data: &mut self.data,
                 };
 
@@ -263,6 +316,11 @@ impl ProgramTracker {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The module that holds the allocator, like `game.exe`.
+    let module = std::env::args()
+        .nth(1)
+        .ok_or("usage: track <module holding the allocator>")?;
+
     let mut pd = ProcessDebugger::new();
     pd.dbg_mut().SetAsync(true);
 
@@ -275,7 +333,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // FInally, create the tracker start tracking allocations and resume the process.
     let mut prog = ProgramTracker::new(p);
-    prog.register_bp_track_allocations();
+    prog.register_bp_track_allocations(&module)?;
     prog.go()?;
     println!("exit");
     Ok(())
@@ -31,6 +31,10 @@ impl std::fmt::Display for TextError {
 ///---
 use std::boxed::Box;
 use std::pin::Pin;
+use std::time::Duration;
+
+/// How long to wait for the state change after stopping or continuing the process.
+const STATE_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Helper to work with lldb
 pub struct TargetProcess {
@@ -78,17 +82,18 @@ impl TargetProcess {
     }
 
     /// Block on waiting for an event.
-    pub fn wait_for_event(&mut self) -> Option<Wrapped<bindings::SBEvent>> {
-        let mut event = bindings::SBEvent::new().wrap();
-        // https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/source/API/SBListener.cpp#L142-L165
-        // returns true only if there was an event to get, always populates the event.
-        let res = self.listener.pin_mut().WaitForEvent(10, event.pin_mut());
-        if res {
-            // println!("event: {event:?}");
-            return Some(event);
-        }
+    pub fn wait_for_event(&mut self, timeout: Duration) -> Option<Wrapped<bindings::SBEvent>> {
+        self.listener.wait_for_event(timeout)
+    }
 
-        None
+    /// Wait for the state change that follows a stop or continue.
+    fn wait_for_state(&mut self) -> Result<bindings::StateType, BError> {
+        match self.wait_for_event(STATE_TIMEOUT) {
+            Some(e) => Ok(e.event_type()),
+            None => Err(Box::new(TextError::new(&format!(
+                "No event within {STATE_TIMEOUT:?}"
+            )))),
+        }
     }
 
     /// Stop the process.
@@ -97,8 +102,7 @@ impl TargetProcess {
         if err.is_fail() {
             return Err(Box::new(err));
         }
-        let e = self.wait_for_event().expect("must get event");
-        let event_type = e.event_type();
+        let event_type = self.wait_for_state()?;
         if event_type == bindings::StateType::eStateStopped {
             return Ok(());
         } else {
@@ -114,8 +118,7 @@ impl TargetProcess {
         if err.is_fail() {
             return Err(Box::new(err));
         }
-        let e = self.wait_for_event().expect("must get event");
-        let event_type = e.event_type();
+        let event_type = self.wait_for_state()?;
         if event_type == bindings::StateType::eStateRunning {
             return Ok(());
         } else {
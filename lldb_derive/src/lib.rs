@@ -0,0 +1,82 @@
+//! Derive macros for the lldb crate.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// The child name from `#[lldb(name = "child")]`, if present.
+fn child_name(attrs: &[syn::Attribute]) -> syn::Result<Option<String>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|a| a.path.is_ident("lldb")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            other => return Err(syn::Error::new_spanned(other, "expected #[lldb(...)]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => match nv.lit {
+                    Lit::Str(s) => name = Some(s.value()),
+                    other => return Err(syn::Error::new_spanned(other, "expected a string")),
+                },
+                other => return Err(syn::Error::new_spanned(other, "unknown lldb attribute")),
+            }
+        }
+    }
+    Ok(name)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromValue can only be derived for structs",
+            ))
+        }
+    };
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let fields = fields
+                .named
+                .iter()
+                .map(|f| {
+                    let ident = f.ident.as_ref().expect("named field");
+                    let child = child_name(&f.attrs)?
+                        .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+                    Ok(quote! { #ident: ::lldb::value::field(value, #child)? })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! { #name { #(#fields),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let fields = (0..fields.unnamed.len() as u32)
+                .map(|i| quote! { ::lldb::value::element(value, #i)? });
+            quote! { #name ( #(#fields),* ) }
+        }
+        Fields::Unit => quote! { { let _ = value; #name } },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::lldb::value::FromValue for #name #ty_generics #where_clause {
+            fn from_value<V: ::lldb::wrappers::Value>(
+                value: &mut V,
+            ) -> ::std::result::Result<Self, ::lldb::value::ValueError> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+/// Implement `FromValue` for a struct, converting each field from the child with the same name.
+#[proc_macro_derive(FromValue, attributes(lldb))]
+pub fn derive_from_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
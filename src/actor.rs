@@ -0,0 +1,330 @@
+use crate::api::ffi::lldb as bindings;
+use crate::autocxx::prelude::*;
+use crate::wrappers::*;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+// None of the SB objects can cross threads, so a single thread owns the debugger and everything
+// created from it. Other threads hold a DebuggerHandle, which sends closures to that thread and
+// waits for their result. Targets, processes and breakpoints are referred to by plain ids that are
+// only resolved into SB objects on the actor thread.
+
+/// Identifies a target owned by the actor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TargetId(u64);
+
+/// Identifies the process of a target, the pid guards against talking to a relaunched process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessHandle {
+    pub target: TargetId,
+    pub pid: u64,
+}
+
+/// Identifies a breakpoint in a target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BreakpointHandle {
+    pub target: TargetId,
+    pub id: BreakpointId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActorError {
+    /// LLDB returned an error, with its description.
+    Lldb(String),
+    /// The id doesn't resolve to a live object on the actor thread.
+    UnknownTarget(TargetId),
+    UnknownProcess(ProcessHandle),
+    UnknownBreakpoint(BreakpointHandle),
+    /// The closure panicked on the actor thread, the actor itself keeps running.
+    Panicked,
+    /// The actor thread is gone.
+    Closed,
+}
+
+impl std::fmt::Display for ActorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActorError::Lldb(e) => write!(f, "lldb error: {e}"),
+            ActorError::UnknownTarget(t) => write!(f, "unknown target {t:?}"),
+            ActorError::UnknownProcess(p) => write!(f, "unknown process {p:?}"),
+            ActorError::UnknownBreakpoint(b) => write!(f, "unknown breakpoint {b:?}"),
+            ActorError::Panicked => write!(f, "closure panicked on the actor thread"),
+            ActorError::Closed => write!(f, "actor thread closed"),
+        }
+    }
+}
+
+impl std::error::Error for ActorError {}
+
+impl From<Wrapped<bindings::SBError>> for ActorError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        ActorError::Lldb(e.get_str().to_string())
+    }
+}
+
+/// Everything owned by the actor thread, handed to the closures sent through the handle.
+pub struct Session {
+    debugger: Wrapped<bindings::SBDebugger>,
+    targets: HashMap<TargetId, Wrapped<bindings::SBTarget>>,
+    next_target: u64,
+}
+
+impl Session {
+    pub fn debugger(&mut self) -> &mut Wrapped<bindings::SBDebugger> {
+        &mut self.debugger
+    }
+
+    /// Take ownership of a target, returning the id it can be referred to by.
+    pub fn add_target(&mut self, target: Wrapped<bindings::SBTarget>) -> TargetId {
+        let id = TargetId(self.next_target);
+        self.next_target += 1;
+        self.targets.insert(id, target);
+        id
+    }
+
+    pub fn target(&mut self, id: TargetId) -> Result<&mut Wrapped<bindings::SBTarget>, ActorError> {
+        self.targets
+            .get_mut(&id)
+            .ok_or(ActorError::UnknownTarget(id))
+    }
+
+    /// Remove the target from the session and the debugger.
+    pub fn remove_target(&mut self, id: TargetId) -> Result<(), ActorError> {
+        let mut target = self
+            .targets
+            .remove(&id)
+            .ok_or(ActorError::UnknownTarget(id))?;
+        self.debugger.delete_target(&mut target);
+        Ok(())
+    }
+
+    /// The current process of the target, as a handle.
+    pub fn process_handle(&mut self, id: TargetId) -> Result<ProcessHandle, ActorError> {
+        let mut process = self.target(id)?.process();
+        if !process.as_ref().IsValid() {
+            return Err(ActorError::Lldb("target has no process".to_string()));
+        }
+        Ok(ProcessHandle {
+            target: id,
+            pid: process.get_process_id(),
+        })
+    }
+
+    pub fn process(
+        &mut self,
+        handle: ProcessHandle,
+    ) -> Result<Wrapped<bindings::SBProcess>, ActorError> {
+        let mut process = self
+            .target(handle.target)
+            .map_err(|_| ActorError::UnknownProcess(handle))?
+            .process();
+        if !process.as_ref().IsValid() || process.get_process_id() != handle.pid {
+            return Err(ActorError::UnknownProcess(handle));
+        }
+        Ok(process)
+    }
+
+    pub fn breakpoint(
+        &mut self,
+        handle: BreakpointHandle,
+    ) -> Result<Wrapped<bindings::SBBreakpoint>, ActorError> {
+        self.target(handle.target)
+            .map_err(|_| ActorError::UnknownBreakpoint(handle))?
+            .find_breakpoint_by_id(handle.id)
+            .ok_or(ActorError::UnknownBreakpoint(handle))
+    }
+}
+
+type Job = Box<dyn FnOnce(&mut Session) + Send>;
+
+/// A cloneable handle to the debugger running on its own thread. The thread stops and destroys the
+/// debugger once all handles are dropped.
+#[derive(Clone)]
+pub struct DebuggerHandle {
+    sender: mpsc::Sender<Job>,
+}
+
+impl DebuggerHandle {
+    /// Spawn the actor thread, creating a debugger in synchronous mode.
+    pub fn spawn() -> DebuggerHandle {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("lldb_rs debugger".to_string())
+            .spawn(move || {
+                bindings::SBDebugger::Initialize();
+                let mut debugger = bindings::SBDebugger::Create().wrap();
+                debugger.pin_mut().SetAsync(false);
+                let mut session = Session {
+                    debugger,
+                    targets: HashMap::new(),
+                    next_target: 0,
+                };
+                // Ends when all senders are dropped.
+                for job in receiver {
+                    job(&mut session);
+                }
+                session.targets.clear();
+                bindings::SBDebugger::Destroy(session.debugger.pin_mut());
+            })
+            .expect("failed to spawn debugger thread");
+        DebuggerHandle { sender }
+    }
+
+    /// Run a closure on the actor thread and wait for its result.
+    pub fn run<R, F>(&self, f: F) -> Result<R, ActorError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Session) -> R + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let job: Job = Box::new(move |session| {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(session)));
+            // The caller may have given up waiting, that's fine.
+            let _ = tx.send(result.map_err(|_| ActorError::Panicked));
+        });
+        self.sender.send(job).map_err(|_| ActorError::Closed)?;
+        rx.recv().map_err(|_| ActorError::Closed)?
+    }
+
+    /// Like run, for closures that can fail themselves.
+    pub fn try_run<R, F>(&self, f: F) -> Result<R, ActorError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Session) -> Result<R, ActorError> + Send + 'static,
+    {
+        self.run(f)?
+    }
+
+    /// Run a command through the command interpreter, returns its output.
+    pub fn execute(&self, command: &str) -> Result<String, ActorError> {
+        let command = command.to_string();
+        self.try_run(move |s| {
+            s.debugger()
+                .handle_command(&command)
+                .map_err(ActorError::Lldb)
+        })
+    }
+
+    /// Create a target for the executable at this path.
+    pub fn create_target(&self, path: &str) -> Result<TargetId, ActorError> {
+        let path = path.to_string();
+        self.try_run(move |s| {
+            let target = s.debugger().create_target(&path)?;
+            Ok(s.add_target(target))
+        })
+    }
+
+    pub fn remove_target(&self, target: TargetId) -> Result<(), ActorError> {
+        self.try_run(move |s| s.remove_target(target))
+    }
+
+    pub fn breakpoint_create_by_name(
+        &self,
+        target: TargetId,
+        name: &str,
+    ) -> Result<BreakpointHandle, ActorError> {
+        let name = name.to_string();
+        self.try_run(move |s| {
+            let bp = s.target(target)?.breakpoint_create_by_name(&name);
+            if !bp.is_valid() {
+                return Err(ActorError::Lldb(format!(
+                    "no breakpoint created for {name}"
+                )));
+            }
+            Ok(BreakpointHandle {
+                target,
+                id: bp.get_id(),
+            })
+        })
+    }
+
+    pub fn breakpoint_create_by_address(
+        &self,
+        target: TargetId,
+        address: u64,
+    ) -> Result<BreakpointHandle, ActorError> {
+        self.try_run(move |s| {
+            let bp = s.target(target)?.breakpoint_create_by_address(address);
+            if !bp.is_valid() {
+                return Err(ActorError::Lldb(format!(
+                    "no breakpoint created at 0x{address:x}"
+                )));
+            }
+            Ok(BreakpointHandle {
+                target,
+                id: bp.get_id(),
+            })
+        })
+    }
+
+    pub fn delete_breakpoint(&self, handle: BreakpointHandle) -> Result<(), ActorError> {
+        self.try_run(move |s| {
+            if !s.target(handle.target)?.delete_breakpoint(handle.id) {
+                return Err(ActorError::UnknownBreakpoint(handle));
+            }
+            Ok(())
+        })
+    }
+
+    /// The state of the process.
+    pub fn process_state(&self, handle: ProcessHandle) -> Result<bindings::StateType, ActorError> {
+        self.try_run(move |s| Ok(s.process(handle)?.get_state()))
+    }
+
+    pub fn resume(&self, handle: ProcessHandle) -> Result<(), ActorError> {
+        self.try_run(move |s| {
+            let mut process = s.process(handle)?;
+            let e = process.pin_mut().Continue().wrap();
+            if e.is_fail() {
+                return Err(e.into());
+            }
+            Ok(())
+        })
+    }
+
+    pub fn kill(&self, handle: ProcessHandle) -> Result<(), ActorError> {
+        self.try_run(move |s| {
+            let mut process = s.process(handle)?;
+            let e = process.pin_mut().Kill().wrap();
+            if e.is_fail() {
+                return Err(e.into());
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_handle_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<DebuggerHandle>();
+        assert_send_sync::<TargetId>();
+        assert_send_sync::<ProcessHandle>();
+        assert_send_sync::<BreakpointHandle>();
+    }
+
+    #[test]
+    fn test_actor() {
+        let handle = DebuggerHandle::spawn();
+        let other = handle.clone();
+        let version = std::thread::spawn(move || other.execute("version"))
+            .join()
+            .unwrap();
+        assert!(version.unwrap().contains("lldb"));
+
+        assert_eq!(
+            handle.remove_target(TargetId(42)),
+            Err(ActorError::UnknownTarget(TargetId(42)))
+        );
+        assert_eq!(
+            handle.run(|_| panic!("boom")),
+            Err::<(), _>(ActorError::Panicked)
+        );
+        // Still alive after the panic.
+        assert_eq!(handle.run(|_| 1 + 1), Ok(2));
+    }
+}
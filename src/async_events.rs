@@ -0,0 +1,231 @@
+use crate::api::ffi::lldb as bindings;
+use crate::events::{DecodedEvent, ProcessEvents};
+use crate::wrappers::*;
+use autocxx::PinMut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// The SB objects are thin handles around shared pointers, LLDB itself guards them with mutexes and
+// the Python bindings use them from many threads. The Rust types are not Send because they hold
+// raw C++ objects, so we assert it here for the objects we move to the event thread or guard with
+// a mutex.
+struct SendBox<T>(T);
+unsafe impl<T> Send for SendBox<T> {}
+impl<T> SendBox<T> {
+    fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// How long the event thread blocks before checking whether it should shut down.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Capacity of the event channel, slow consumers lose the oldest events.
+const EVENT_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsyncError {
+    /// LLDB returned an error, with its description.
+    Lldb(String),
+    /// The event thread is gone.
+    Closed,
+}
+
+impl std::fmt::Display for AsyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AsyncError::Lldb(e) => write!(f, "lldb error: {e}"),
+            AsyncError::Closed => write!(f, "event thread closed"),
+        }
+    }
+}
+
+impl std::error::Error for AsyncError {}
+
+fn check(err: Wrapped<bindings::SBError>) -> Result<(), AsyncError> {
+    if err.is_fail() {
+        return Err(AsyncError::Lldb(err.get_str().to_string()));
+    }
+    Ok(())
+}
+
+/// Whether the process is in a state where it won't change by itself anymore.
+fn is_halted(state: &bindings::StateType) -> bool {
+    matches!(
+        state,
+        bindings::StateType::eStateStopped
+            | bindings::StateType::eStateCrashed
+            | bindings::StateType::eStateExited
+            | bindings::StateType::eStateDetached
+            | bindings::StateType::eStateSuspended
+    )
+}
+
+/// A process whose events are received on a dedicated thread, providing a stream of decoded events
+/// and async process control. The debugger must be in asynchronous mode.
+pub struct AsyncProcess {
+    process: Mutex<SendBox<Wrapped<bindings::SBProcess>>>,
+    events: tokio::sync::broadcast::Sender<DecodedEvent>,
+    /// Sequence number of the state change and the state itself.
+    state: tokio::sync::watch::Receiver<(u64, bindings::StateType)>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl AsyncProcess {
+    /// Create a dedicated listener for the process' events.
+    pub fn new(process: Wrapped<bindings::SBProcess>) -> AsyncProcess {
+        let mut listener = Wrapped::<bindings::SBListener>::with_name("lldb_rs.async_process");
+        listener.start_listening_for_events(&process.broadcaster(), ProcessEvents::all());
+        AsyncProcess::with_listener(process, listener)
+    }
+
+    /// Use an existing listener, like the one the process was launched or attached with. The
+    /// listener is moved to the event thread, so it should not be used elsewhere.
+    pub fn with_listener(
+        mut process: Wrapped<bindings::SBProcess>,
+        listener: Wrapped<bindings::SBListener>,
+    ) -> AsyncProcess {
+        let (events_tx, _) = tokio::sync::broadcast::channel(EVENT_CAPACITY);
+        let (state_tx, state_rx) = tokio::sync::watch::channel((0, process.get_state()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let listener = SendBox(listener);
+        let thread_events = events_tx.clone();
+        let thread_shutdown = shutdown.clone();
+        std::thread::Builder::new()
+            .name("lldb_rs events".to_string())
+            .spawn(move || {
+                let mut listener = listener.into_inner();
+                while !thread_shutdown.load(Ordering::Relaxed) {
+                    let event = match listener.wait_for_event(POLL_INTERVAL) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+                    let decoded = event.decode();
+                    if let DecodedEvent::ProcessStateChanged { state, restarted } = &decoded {
+                        if !restarted {
+                            state_tx.send_modify(|v| {
+                                v.0 += 1;
+                                v.1 = state.clone();
+                            });
+                        }
+                    }
+                    // No receivers is fine, nobody is interested in the stream.
+                    let _ = thread_events.send(decoded);
+                }
+            })
+            .expect("failed to spawn event thread");
+
+        AsyncProcess {
+            process: Mutex::new(SendBox(process)),
+            events: events_tx,
+            state: state_rx,
+            shutdown,
+        }
+    }
+
+    /// Stream of all events received from now on.
+    pub fn events(&self) -> impl futures::Stream<Item = DecodedEvent> + Send + 'static {
+        let rx = self.events.subscribe();
+        futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// The most recently observed process state.
+    pub fn state(&self) -> bindings::StateType {
+        self.state.borrow().1.clone()
+    }
+
+    /// Run a closure with the process, for everything that isn't covered by the async methods.
+    pub fn with_process<R, F: FnOnce(&mut Wrapped<bindings::SBProcess>) -> R>(&self, f: F) -> R {
+        let mut process = self.process.lock().expect("process mutex poisoned");
+        f(&mut process.0)
+    }
+
+    /// Wait for the next state change, returning the new state.
+    async fn next_state(&self, seq: u64) -> Result<bindings::StateType, AsyncError> {
+        let mut rx = self.state.clone();
+        loop {
+            let (current, state) = rx.borrow_and_update().clone();
+            if current != seq {
+                return Ok(state);
+            }
+            rx.changed().await.map_err(|_| AsyncError::Closed)?;
+        }
+    }
+
+    /// Continue the process, returns the state it transitioned to, usually running.
+    pub async fn resume(&self) -> Result<bindings::StateType, AsyncError> {
+        let seq = self.state.borrow().0;
+        self.with_process(|p| check(p.pin_mut().Continue().wrap()))?;
+        self.next_state(seq).await
+    }
+
+    /// Interrupt the process and wait until it has stopped.
+    pub async fn stop(&self) -> Result<bindings::StateType, AsyncError> {
+        self.with_process(|p| check(p.pin_mut().Stop().wrap()))?;
+        self.wait_until_stopped().await
+    }
+
+    /// Wait until the process is stopped, crashed, exited or detached, returns immediately if it
+    /// already is.
+    pub async fn wait_until_stopped(&self) -> Result<bindings::StateType, AsyncError> {
+        let mut rx = self.state.clone();
+        loop {
+            let state = rx.borrow_and_update().1.clone();
+            if is_halted(&state) {
+                return Ok(state);
+            }
+            rx.changed().await.map_err(|_| AsyncError::Closed)?;
+        }
+    }
+}
+
+impl Drop for AsyncProcess {
+    fn drop(&mut self) {
+        // The thread notices this within the poll interval.
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn test_is_halted() {
+        assert!(is_halted(&bindings::StateType::eStateStopped));
+        assert!(is_halted(&bindings::StateType::eStateExited));
+        assert!(!is_halted(&bindings::StateType::eStateRunning));
+        assert!(!is_halted(&bindings::StateType::eStateInvalid));
+    }
+
+    #[test]
+    fn test_state_without_process() {
+        bindings::SBDebugger::Initialize();
+        let process = bindings::SBProcess::new().wrap();
+        let listener = Wrapped::<bindings::SBListener>::with_name("lldb_rs.test");
+        let async_process = AsyncProcess::with_listener(process, listener);
+
+        // The initial state comes from the process, without any events.
+        assert_eq!(async_process.state(), bindings::StateType::eStateInvalid);
+        assert!(!async_process.with_process(|p| p.as_ref().IsValid()));
+
+        // An invalid process never halts, nor does it resume.
+        assert!(async_process.wait_until_stopped().now_or_never().is_none());
+        assert!(matches!(
+            futures::executor::block_on(async_process.resume()),
+            Err(AsyncError::Lldb(_))
+        ));
+        assert_eq!(async_process.state(), bindings::StateType::eStateInvalid);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::symbolication::Symbolicator;
+use crate::wrappers::*;
+
+// A backtrace is captured from the frames LLDB unwinds for a thread, each frame is reduced to plain
+// data right away such that backtraces can be kept around after the process continued. Inlined
+// functions show up as separate frames sharing the pc of the frame they were inlined into.
+
+/// FNV-1a, used for hashes that have to be the same across runs and Rust versions.
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// A single frame of a backtrace, fields are None if they couldn't be resolved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BacktraceFrame {
+    pub pc: u64,
+    /// The function name, the inlined function for inlined frames.
+    pub symbol: Option<String>,
+    /// File name of the module, like `kernel32.dll`.
+    pub module: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// This function got inlined into the next frame.
+    pub inlined: bool,
+}
+
+/// The call stack of a thread, innermost frame first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Backtrace {
+    pub frames: Vec<BacktraceFrame>,
+    /// The thread had more frames than were captured.
+    pub truncated: bool,
+    /// Number of innermost frames that were left out, if any then the pc of every frame is a
+    /// return address.
+    pub skipped: usize,
+}
+
+impl Backtrace {
+    /// A backtrace of bare program counters, innermost first.
+    pub fn from_pcs(pcs: &[u64]) -> Backtrace {
+        Backtrace {
+            frames: pcs
+                .iter()
+                .map(|pc| BacktraceFrame {
+                    pc: *pc,
+                    ..Default::default()
+                })
+                .collect(),
+            truncated: false,
+            skipped: 0,
+        }
+    }
+
+    pub fn pcs(&self) -> Vec<u64> {
+        self.frames.iter().map(|f| f.pc).collect()
+    }
+
+    /// Resolve the frames that are bare program counters, functions inlined at a pc become frames
+    /// of their own like in a captured backtrace. The symbolicator caches the lookups, such that
+    /// a pc shared by many backtraces is resolved once.
+    /// Except for the innermost frame of the thread, the pcs are return addresses which can be
+    /// past the end of the calling function or line. Like LLDB does for its frames, those are
+    /// looked up at `pc - 1`, the frames keep the original pc.
+    pub fn symbolicate<T: Target>(
+        &self,
+        target: &mut T,
+        symbolicator: &mut Symbolicator,
+    ) -> Backtrace {
+        let mut frames = vec![];
+        for (i, frame) in self.frames.iter().enumerate() {
+            if frame.symbol.is_some() {
+                frames.push(frame.clone());
+                continue;
+            }
+            let address = if i == 0 && self.skipped == 0 {
+                frame.pc
+            } else {
+                frame.pc.saturating_sub(1)
+            };
+            let info = symbolicator.symbolicate(target, address);
+            let (mut file, mut line) = (info.file.clone(), info.line);
+            for inlined in info.inlined_chain.iter() {
+                frames.push(BacktraceFrame {
+                    pc: frame.pc,
+                    symbol: Some(inlined.function.clone()),
+                    module: info.module.clone(),
+                    file,
+                    line,
+                    inlined: true,
+                });
+                // The next frame out is where this one got inlined.
+                file = inlined.call_file.clone();
+                line = inlined.call_line;
+            }
+            frames.push(BacktraceFrame {
+                pc: frame.pc,
+                symbol: info.function.clone().or_else(|| info.symbol.clone()),
+                module: info.module.clone(),
+                file,
+                line,
+                inlined: false,
+            });
+        }
+        Backtrace {
+            frames,
+            truncated: self.truncated,
+            skipped: self.skipped,
+        }
+    }
+
+    /// A hash for grouping identical stacks that stays the same across runs. Frames with a symbol
+    /// are hashed by module, symbol and line, such that the hash doesn't change if a module gets
+    /// loaded at another address. Frames without a symbol are hashed by their pc.
+    pub fn stable_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        for frame in self.frames.iter() {
+            match &frame.symbol {
+                Some(symbol) => {
+                    hash = fnv1a(hash, frame.module.as_deref().unwrap_or("").as_bytes());
+                    hash = fnv1a(hash, &[0]);
+                    hash = fnv1a(hash, symbol.as_bytes());
+                    hash = fnv1a(hash, &[0]);
+                    hash = fnv1a(hash, &frame.line.unwrap_or(0).to_le_bytes());
+                }
+                None => hash = fnv1a(hash, &frame.pc.to_le_bytes()),
+            }
+            hash = fnv1a(hash, &[frame.inlined as u8]);
+        }
+        hash
+    }
+}
+
+/// Formats like the frames of LLDB's `bt`, one line per frame.
+impl std::fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, frame) in self.frames.iter().enumerate() {
+            write!(f, "frame #{i}: 0x{:016x}", frame.pc)?;
+            if let Some(module) = &frame.module {
+                write!(f, " {module}`")?;
+            } else if frame.symbol.is_some() {
+                write!(f, " ")?;
+            }
+            if let Some(symbol) = &frame.symbol {
+                write!(f, "{symbol}")?;
+            }
+            if frame.inlined {
+                write!(f, " [inlined]")?;
+            }
+            if let Some(file) = &frame.file {
+                let file = file.rsplit(['/', '\\']).next().unwrap_or(file);
+                write!(f, " at {file}")?;
+                if let Some(line) = frame.line {
+                    write!(f, ":{line}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        if self.truncated {
+            writeln!(f, "...")?;
+        }
+        Ok(())
+    }
+}
+
+fn frame_info<F: Frame>(frame: &mut F) -> BacktraceFrame {
+    let module = frame.get_module();
+    let line_entry = frame.get_line_entry();
+    let (file, line) = if line_entry.is_valid() {
+        let file = line_entry.get_file_spec();
+        let line = line_entry.get_line();
+        (
+            file.is_valid().then(|| file.get_path()),
+            (line != 0).then_some(line),
+        )
+    } else {
+        (None, None)
+    };
+    BacktraceFrame {
+        pc: frame.get_pc(),
+        symbol: frame.get_function_name(),
+        module: if module.is_valid() {
+            module.get_file_spec().get_filename()
+        } else {
+            None
+        },
+        file,
+        line,
+        inlined: frame.is_inlined(),
+    }
+}
+
+/// Capture up to `max_frames` frames of the thread, leaving out the `skip` innermost frames.
+pub fn capture<T: Thread>(thread: &mut T, skip: usize, max_frames: usize) -> Backtrace {
+    let count = thread.get_num_frames() as usize;
+    let end = count.min(skip.saturating_add(max_frames));
+    Backtrace {
+        frames: (skip..end)
+            .map(|i| frame_info(&mut thread.frame(i as u32)))
+            .collect(),
+        truncated: end < count,
+        skipped: skip.min(count),
+    }
+}
+
+/// Like [`capture`], but only records the program counters, which is a lot cheaper when
+/// backtraces are taken at every hit of a breakpoint. Inlined frames are left out as they share
+/// the pc of the frame they got inlined into, [`Backtrace::symbolicate`] restores them.
+pub fn capture_pcs<T: Thread>(thread: &mut T, skip: usize, max_frames: usize) -> Backtrace {
+    let mut pcs = vec![];
+    let mut skipped = 0;
+    let mut truncated = false;
+    for i in 0..thread.get_num_frames() {
+        let mut frame = thread.frame(i);
+        if frame.is_inlined() {
+            continue;
+        }
+        if skipped < skip {
+            skipped += 1;
+            continue;
+        }
+        if pcs.len() == max_frames {
+            truncated = true;
+            break;
+        }
+        pcs.push(frame.get_pc());
+    }
+    Backtrace {
+        truncated,
+        skipped,
+        ..Backtrace::from_pcs(&pcs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::ffi::lldb as bindings;
+    use autocxx::prelude::*;
+
+    fn frame(pc: u64, symbol: &str, line: u32) -> BacktraceFrame {
+        BacktraceFrame {
+            pc,
+            symbol: Some(symbol.to_string()),
+            module: Some("game.exe".to_string()),
+            file: Some("C:\\src\\game\\world.c".to_string()),
+            line: Some(line),
+            inlined: false,
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let mut bt = Backtrace {
+            frames: vec![frame(0x401000, "spawn", 12), frame(0x402000, "main", 40)],
+            truncated: true,
+            skipped: 0,
+        };
+        bt.frames[0].inlined = true;
+        bt.frames.push(BacktraceFrame {
+            pc: 0x7bc5d1e0,
+            ..Default::default()
+        });
+        assert_eq!(
+            bt.to_string(),
+            "frame #0: 0x0000000000401000 game.exe`spawn [inlined] at world.c:12\n\
+             frame #1: 0x0000000000402000 game.exe`main at world.c:40\n\
+             frame #2: 0x000000007bc5d1e0\n\
+             ...\n"
+        );
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(Backtrace::default().stable_hash(), FNV_OFFSET);
+        let a = Backtrace {
+            frames: vec![frame(0x401000, "spawn", 12), frame(0x402000, "main", 40)],
+            truncated: false,
+            skipped: 0,
+        };
+        // Same code loaded at another address.
+        let mut b = a.clone();
+        b.frames[0].pc += 0x10000;
+        b.frames[1].pc += 0x10000;
+        assert_eq!(a.stable_hash(), b.stable_hash());
+        b.frames[0].line = Some(13);
+        assert_ne!(a.stable_hash(), b.stable_hash());
+
+        let raw = Backtrace::from_pcs(&[0x401000, 0x402000]);
+        assert_eq!(raw.pcs(), vec![0x401000, 0x402000]);
+        assert_ne!(
+            raw.stable_hash(),
+            Backtrace::from_pcs(&[0x401000, 0x402001]).stable_hash()
+        );
+    }
+
+    #[test]
+    fn test_symbolicate_return_addresses() {
+        bindings::SBDebugger::Initialize();
+        let mut dbg = bindings::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+
+        // The innermost frame is looked up at its pc, its callers before their return address.
+        let mut symbolicator = Symbolicator::new();
+        let bt =
+            Backtrace::from_pcs(&[0x401000, 0x402000]).symbolicate(&mut target, &mut symbolicator);
+        assert_eq!(bt.pcs(), vec![0x401000, 0x402000]);
+        assert_eq!(symbolicator.len(), 2);
+        symbolicator.symbolicate(&mut target, 0x401000);
+        symbolicator.symbolicate(&mut target, 0x401fff);
+        assert_eq!(symbolicator.len(), 2);
+
+        // Without the innermost frame every pc is a return address.
+        let mut symbolicator = Symbolicator::new();
+        let skipped = Backtrace {
+            skipped: 1,
+            ..Backtrace::from_pcs(&[0x401000])
+        };
+        let bt = skipped.symbolicate(&mut target, &mut symbolicator);
+        assert_eq!(bt.pcs(), vec![0x401000]);
+        symbolicator.symbolicate(&mut target, 0x400fff);
+        assert_eq!(symbolicator.len(), 1);
+    }
+}
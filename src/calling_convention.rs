@@ -0,0 +1,434 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+use std::collections::HashMap;
+
+// Calling conventions describe where the integer and pointer arguments live at the first
+// instruction of a function, before the prologue has touched the stack. All values are only
+// valid at that point, for the return value the frame must be at the return address.
+// Floating point and aggregate arguments are not handled.
+
+/// Architecture of a target, derived from the target triple.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arch {
+    X86,
+    X86_64,
+    Arm,
+    Aarch64,
+    Unknown,
+}
+
+impl Arch {
+    pub fn from_triple(triple: &str) -> Arch {
+        let arch = triple.split('-').next().unwrap_or("");
+        match arch {
+            "i386" | "i486" | "i586" | "i686" | "x86" => Arch::X86,
+            "x86_64" | "amd64" => Arch::X86_64,
+            "aarch64" | "arm64" | "arm64e" => Arch::Aarch64,
+            a if a.starts_with("arm") || a.starts_with("thumb") => Arch::Arm,
+            _ => Arch::Unknown,
+        }
+    }
+
+    /// Size of a pointer in bytes.
+    pub fn pointer_size(&self) -> Option<usize> {
+        match self {
+            Arch::X86 | Arch::Arm => Some(4),
+            Arch::X86_64 | Arch::Aarch64 => Some(8),
+            Arch::Unknown => None,
+        }
+    }
+}
+
+/// Where the return address can be found at function entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReturnAddress {
+    /// At the top of the stack.
+    Stack,
+    /// In a link register.
+    Register(&'static str),
+}
+
+/// Description of where arguments are placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub pointer_size: usize,
+    /// Registers used for the first arguments, in order.
+    pub arg_registers: &'static [&'static str],
+    /// Offset from the stack pointer to the first argument passed on the stack.
+    pub first_stack_arg: u64,
+    pub return_register: &'static str,
+    pub return_address: ReturnAddress,
+}
+
+impl Layout {
+    /// Location of the n'th argument, either a register name or a stack address.
+    pub fn arg_location(&self, sp: u64, n: usize) -> ArgLocation {
+        if n < self.arg_registers.len() {
+            return ArgLocation::Register(self.arg_registers[n]);
+        }
+        let index = (n - self.arg_registers.len()) as u64;
+        ArgLocation::Stack(sp + self.first_stack_arg + index * self.pointer_size as u64)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ArgLocation {
+    Register(&'static str),
+    Stack(u64),
+}
+
+/// Conversion from a raw argument or return value, truncating to the size of the type.
+pub trait FromArg {
+    fn from_arg(raw: u64) -> Self;
+}
+
+macro_rules! impl_from_arg {
+    ($($t:ty),*) => {
+        $(
+            impl FromArg for $t {
+                fn from_arg(raw: u64) -> Self {
+                    raw as $t
+                }
+            }
+        )*
+    };
+}
+impl_from_arg!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl FromArg for bool {
+    fn from_arg(raw: u64) -> Self {
+        raw as u8 != 0
+    }
+}
+
+/// Read a little endian pointer sized value from the process memory.
+pub(crate) fn read_pointer<F: Frame>(frame: &mut F, address: u64, size: usize) -> SBResult<u64> {
+    let data = frame.thread().process().read_memory(address, size)?;
+    if data.len() != size || size == 0 || size > 8 {
+        return Err(Wrapped::<bindings::SBError>::from_message(&format!(
+            "could not read {size} bytes at 0x{address:x}"
+        )));
+    }
+    let mut bytes = [0u8; 8];
+    bytes[..size].copy_from_slice(&data);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub trait CallingConvention {
+    fn layout(&self) -> &'static Layout;
+
+    /// Raw value of the n'th integer or pointer argument, zero based.
+    fn arg<F: Frame>(&self, frame: &mut F, n: usize) -> SBResult<u64> {
+        let layout = self.layout();
+        match layout.arg_location(frame.get_sp(), n) {
+            ArgLocation::Register(name) => frame.find_register(name).get_value_unsigned(),
+            ArgLocation::Stack(address) => read_pointer(frame, address, layout.pointer_size),
+        }
+    }
+
+    /// The n'th argument, converted to the requested type.
+    fn arg_as<T: FromArg, F: Frame>(&self, frame: &mut F, n: usize) -> SBResult<T> {
+        Ok(T::from_arg(self.arg(frame, n)?))
+    }
+
+    /// The integer or pointer return value, only valid when stopped at the return address.
+    fn return_value<F: Frame>(&self, frame: &mut F) -> SBResult<u64> {
+        frame
+            .find_register(self.layout().return_register)
+            .get_value_unsigned()
+    }
+
+    /// The address this function will return to.
+    fn return_address<F: Frame>(&self, frame: &mut F) -> SBResult<u64> {
+        let layout = self.layout();
+        match layout.return_address {
+            ReturnAddress::Stack => {
+                let sp = frame.get_sp();
+                read_pointer(frame, sp, layout.pointer_size)
+            }
+            ReturnAddress::Register(name) => frame.find_register(name).get_value_unsigned(),
+        }
+    }
+}
+
+/// 32 bits x86, all arguments on the stack, caller cleans up.
+pub struct Cdecl;
+static CDECL: Layout = Layout {
+    pointer_size: 4,
+    arg_registers: &[],
+    first_stack_arg: 4,
+    return_register: "eax",
+    return_address: ReturnAddress::Stack,
+};
+impl CallingConvention for Cdecl {
+    fn layout(&self) -> &'static Layout {
+        &CDECL
+    }
+}
+
+/// 32 bits x86 as used by the Windows API, identical to cdecl for the arguments, callee cleans up.
+pub struct Stdcall;
+impl CallingConvention for Stdcall {
+    fn layout(&self) -> &'static Layout {
+        &CDECL
+    }
+}
+
+/// 32 bits x86 Microsoft fastcall, first two arguments in ecx and edx.
+pub struct Fastcall;
+static FASTCALL: Layout = Layout {
+    pointer_size: 4,
+    arg_registers: &["ecx", "edx"],
+    first_stack_arg: 4,
+    return_register: "eax",
+    return_address: ReturnAddress::Stack,
+};
+impl CallingConvention for Fastcall {
+    fn layout(&self) -> &'static Layout {
+        &FASTCALL
+    }
+}
+
+/// 64 bits Windows, four register arguments followed by 32 bytes of shadow space.
+pub struct Win64;
+static WIN64: Layout = Layout {
+    pointer_size: 8,
+    arg_registers: &["rcx", "rdx", "r8", "r9"],
+    first_stack_arg: 8 + 32,
+    return_register: "rax",
+    return_address: ReturnAddress::Stack,
+};
+impl CallingConvention for Win64 {
+    fn layout(&self) -> &'static Layout {
+        &WIN64
+    }
+}
+
+/// System V x86-64, used by Linux and macOS.
+pub struct SysV64;
+static SYSV64: Layout = Layout {
+    pointer_size: 8,
+    arg_registers: &["rdi", "rsi", "rdx", "rcx", "r8", "r9"],
+    first_stack_arg: 8,
+    return_register: "rax",
+    return_address: ReturnAddress::Stack,
+};
+impl CallingConvention for SysV64 {
+    fn layout(&self) -> &'static Layout {
+        &SYSV64
+    }
+}
+
+/// 32 bits ARM procedure call standard, four register arguments and the return address in the link
+/// register.
+pub struct Aapcs32;
+static AAPCS32: Layout = Layout {
+    pointer_size: 4,
+    arg_registers: &["r0", "r1", "r2", "r3"],
+    first_stack_arg: 0,
+    return_register: "r0",
+    return_address: ReturnAddress::Register("lr"),
+};
+impl CallingConvention for Aapcs32 {
+    fn layout(&self) -> &'static Layout {
+        &AAPCS32
+    }
+}
+
+/// AArch64 procedure call standard, return address in the link register.
+pub struct Aapcs64;
+static AAPCS64: Layout = Layout {
+    pointer_size: 8,
+    arg_registers: &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"],
+    first_stack_arg: 0,
+    return_register: "x0",
+    return_address: ReturnAddress::Register("lr"),
+};
+impl CallingConvention for Aapcs64 {
+    fn layout(&self) -> &'static Layout {
+        &AAPCS64
+    }
+}
+
+/// Selectable calling convention.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Convention {
+    Cdecl,
+    Stdcall,
+    Fastcall,
+    Win64,
+    SysV64,
+    Aapcs32,
+    Aapcs64,
+}
+
+impl Convention {
+    /// Select the default convention for a target triple.
+    pub fn from_triple(triple: &str) -> Option<Convention> {
+        let windows = triple.contains("windows") || triple.contains("mingw");
+        match Arch::from_triple(triple) {
+            Arch::X86 if windows => Some(Convention::Stdcall),
+            Arch::X86 => Some(Convention::Cdecl),
+            Arch::X86_64 if windows => Some(Convention::Win64),
+            Arch::X86_64 => Some(Convention::SysV64),
+            Arch::Arm => Some(Convention::Aapcs32),
+            Arch::Aarch64 => Some(Convention::Aapcs64),
+            Arch::Unknown => None,
+        }
+    }
+
+    /// Select the default convention for a target.
+    pub fn for_target<T: Target>(target: &mut T) -> Option<Convention> {
+        Convention::from_triple(&target.get_triple())
+    }
+}
+
+impl CallingConvention for Convention {
+    fn layout(&self) -> &'static Layout {
+        match self {
+            Convention::Cdecl => Cdecl.layout(),
+            Convention::Stdcall => Stdcall.layout(),
+            Convention::Fastcall => Fastcall.layout(),
+            Convention::Win64 => Win64.layout(),
+            Convention::SysV64 => SysV64.layout(),
+            Convention::Aapcs32 => Aapcs32.layout(),
+            Convention::Aapcs64 => Aapcs64.layout(),
+        }
+    }
+}
+
+/// A default convention with per-function overrides, for processes that mix conventions like
+/// Wine, where the Windows side uses stdcall and the unix side cdecl.
+#[derive(Debug, Clone)]
+pub struct Conventions {
+    default: Convention,
+    by_address: HashMap<u64, Convention>,
+    by_name: HashMap<String, Convention>,
+}
+
+impl Conventions {
+    pub fn new(default: Convention) -> Conventions {
+        Conventions {
+            default,
+            by_address: HashMap::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    /// Use the default convention for the target's triple.
+    pub fn for_target<T: Target>(target: &mut T) -> Option<Conventions> {
+        Convention::for_target(target).map(Conventions::new)
+    }
+
+    /// Override the convention for the function starting at this address.
+    pub fn set_for_address(&mut self, function: u64, convention: Convention) -> &mut Self {
+        self.by_address.insert(function, convention);
+        self
+    }
+
+    /// Override the convention for functions with this name.
+    pub fn set_for_name(&mut self, function: &str, convention: Convention) -> &mut Self {
+        self.by_name.insert(function.to_string(), convention);
+        self
+    }
+
+    pub fn default_convention(&self) -> Convention {
+        self.default
+    }
+
+    /// The convention for the function this frame is in, the frame must be at the function entry
+    /// for address overrides to match.
+    pub fn for_frame<F: Frame>(&self, frame: &mut F) -> Convention {
+        if let Some(c) = self.by_address.get(&frame.get_pc()) {
+            return *c;
+        }
+        if !self.by_name.is_empty() {
+            if let Some(c) = frame
+                .get_function_name()
+                .and_then(|name| self.by_name.get(&name))
+            {
+                return *c;
+            }
+        }
+        self.default
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_triple() {
+        assert_eq!(Arch::from_triple("x86_64-pc-linux-gnu"), Arch::X86_64);
+        assert_eq!(Arch::from_triple("i386-pc-windows-msvc"), Arch::X86);
+        assert_eq!(Arch::from_triple("aarch64-apple-macosx"), Arch::Aarch64);
+        assert_eq!(
+            Convention::from_triple("x86_64-pc-linux-gnu"),
+            Some(Convention::SysV64)
+        );
+        assert_eq!(
+            Convention::from_triple("x86_64-pc-windows-msvc"),
+            Some(Convention::Win64)
+        );
+        assert_eq!(
+            Convention::from_triple("i686-pc-windows-msvc"),
+            Some(Convention::Stdcall)
+        );
+        assert_eq!(
+            Convention::from_triple("i386-pc-linux-gnu"),
+            Some(Convention::Cdecl)
+        );
+        assert_eq!(
+            Convention::from_triple("armv7-unknown-linux-gnueabihf"),
+            Some(Convention::Aapcs32)
+        );
+        assert_eq!(
+            Convention::from_triple("thumbv7-pc-windows-msvc"),
+            Some(Convention::Aapcs32)
+        );
+        assert_eq!(Convention::from_triple("riscv64-unknown-linux-gnu"), None);
+    }
+
+    #[test]
+    fn test_arg_location() {
+        // cdecl, first argument directly above the return address.
+        assert_eq!(
+            Cdecl.layout().arg_location(0x1000, 0),
+            ArgLocation::Stack(0x1004)
+        );
+        assert_eq!(
+            Fastcall.layout().arg_location(0x1000, 1),
+            ArgLocation::Register("edx")
+        );
+        assert_eq!(
+            Fastcall.layout().arg_location(0x1000, 2),
+            ArgLocation::Stack(0x1004)
+        );
+        // Win64 fifth argument lives after the return address and the shadow space.
+        assert_eq!(
+            Win64.layout().arg_location(0x1000, 4),
+            ArgLocation::Stack(0x1028)
+        );
+        assert_eq!(
+            SysV64.layout().arg_location(0x1000, 6),
+            ArgLocation::Stack(0x1008)
+        );
+        assert_eq!(
+            Aapcs32.layout().arg_location(0x1000, 5),
+            ArgLocation::Stack(0x1004)
+        );
+        assert_eq!(
+            Aapcs64.layout().arg_location(0x1000, 8),
+            ArgLocation::Stack(0x1000)
+        );
+    }
+
+    #[test]
+    fn test_from_arg() {
+        assert_eq!(i32::from_arg(0xffff_ffff), -1);
+        assert_eq!(u8::from_arg(0x1234), 0x34);
+        assert!(bool::from_arg(1));
+        assert!(!bool::from_arg(0x100));
+    }
+}
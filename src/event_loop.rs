@@ -0,0 +1,491 @@
+use crate::api::ffi::lldb as bindings;
+use crate::events::{DecodedEvent, ProcessEvents, TargetEvents};
+use crate::wrappers::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// The loop listens on its own listener for process state changes, output and module loads. When
+// the process stops every thread with a stop reason is dispatched to the matching handler, the
+// results are combined with Detach taking precedence over Stop and Stop over Continue. The debugger
+// must be in asynchronous mode.
+
+/// What to do with the process after a handler ran.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Flow {
+    Continue,
+    Stop,
+    Detach,
+}
+
+/// Why the loop returned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// A handler asked to stop, the process is stopped and the loop can be run again.
+    Stopped,
+    /// The process exited with this exit code.
+    Exited(i32),
+    Detached,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventLoopError {
+    /// LLDB returned an error, with its description.
+    Lldb(String),
+    /// No event was received within the timeout.
+    Timeout,
+}
+
+impl std::fmt::Display for EventLoopError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EventLoopError::Lldb(e) => write!(f, "lldb error: {e}"),
+            EventLoopError::Timeout => write!(f, "no event within the timeout"),
+        }
+    }
+}
+
+impl std::error::Error for EventLoopError {}
+
+impl From<Wrapped<bindings::SBError>> for EventLoopError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        EventLoopError::Lldb(e.get_str().to_string())
+    }
+}
+
+/// The thread that stopped, handed to the stop handlers.
+pub struct Stop<'a> {
+    pub process: &'a mut Wrapped<bindings::SBProcess>,
+    pub thread: &'a mut Wrapped<bindings::SBThread>,
+}
+
+type StopHandler = Box<dyn FnMut(&mut Stop) -> Flow>;
+type SignalHandler = Box<dyn FnMut(&mut Stop, i32) -> Flow>;
+type ExceptionHandler = Box<dyn FnMut(&mut Stop, &str) -> Flow>;
+type ModuleHandler = Box<dyn FnMut(&mut Wrapped<bindings::SBProcess>, &[String]) -> Flow>;
+type OutputHandler = Box<dyn FnMut(&mut Wrapped<bindings::SBProcess>, &[u8]) -> Flow>;
+type ExitHandler = Box<dyn FnMut(i32)>;
+
+/// Why a thread stopped, with what its handler gets passed.
+#[derive(Debug, Clone, PartialEq)]
+enum StopCause {
+    Breakpoints(Vec<BreakpointId>),
+    Watchpoint(Option<WatchpointId>),
+    Signal(i32),
+    Exception(String),
+    /// The thread merely got halted along with the others.
+    Halted,
+    Other,
+}
+
+impl StopCause {
+    fn of(thread: &mut Wrapped<bindings::SBThread>) -> StopCause {
+        match thread.get_stop_reason() {
+            bindings::StopReason::eStopReasonBreakpoint => {
+                StopCause::Breakpoints(thread.stop_breakpoint_ids())
+            }
+            bindings::StopReason::eStopReasonWatchpoint => {
+                StopCause::Watchpoint(thread.stop_watchpoint_id())
+            }
+            bindings::StopReason::eStopReasonSignal => {
+                StopCause::Signal(thread.stop_signal().unwrap_or_default())
+            }
+            bindings::StopReason::eStopReasonException => {
+                StopCause::Exception(thread.get_stop_description())
+            }
+            bindings::StopReason::eStopReasonNone | bindings::StopReason::eStopReasonInvalid => {
+                StopCause::Halted
+            }
+            _ => StopCause::Other,
+        }
+    }
+}
+
+/// The handlers for threads that stopped.
+struct StopHandlers {
+    unhandled: Flow,
+    breakpoints: HashMap<BreakpointId, StopHandler>,
+    watchpoints: HashMap<WatchpointId, StopHandler>,
+    signal: Option<SignalHandler>,
+    exception: Option<ExceptionHandler>,
+}
+
+impl StopHandlers {
+    fn new() -> StopHandlers {
+        StopHandlers {
+            unhandled: Flow::Stop,
+            breakpoints: HashMap::new(),
+            watchpoints: HashMap::new(),
+            signal: None,
+            exception: None,
+        }
+    }
+
+    /// Run the handlers for a thread, None if the thread has nothing to handle.
+    fn dispatch(&mut self, stop: &mut Stop, cause: &StopCause) -> Option<Flow> {
+        let flow = match cause {
+            StopCause::Breakpoints(ids) => {
+                let mut flow = None;
+                for id in ids {
+                    let f = match self.breakpoints.get_mut(id) {
+                        Some(handler) => handler(stop),
+                        None => self.unhandled,
+                    };
+                    flow = flow.max(Some(f));
+                }
+                flow.unwrap_or(self.unhandled)
+            }
+            StopCause::Watchpoint(id) => match id.and_then(|id| self.watchpoints.get_mut(&id)) {
+                Some(handler) => handler(stop),
+                None => self.unhandled,
+            },
+            StopCause::Signal(signal) => match &mut self.signal {
+                Some(handler) => handler(stop, *signal),
+                None => self.unhandled,
+            },
+            StopCause::Exception(description) => match &mut self.exception {
+                Some(handler) => handler(stop, description),
+                None => self.unhandled,
+            },
+            StopCause::Halted => return None,
+            StopCause::Other => self.unhandled,
+        };
+        Some(flow)
+    }
+}
+
+/// What the loop returns once the handlers for a stop ran, None to resume the process.
+fn stop_outcome(flow: Flow) -> Option<Outcome> {
+    match flow {
+        Flow::Continue => None,
+        Flow::Stop => Some(Outcome::Stopped),
+        Flow::Detach => Some(Outcome::Detached),
+    }
+}
+
+/// Dispatches process events to registered handlers until the process exits or a handler asks to
+/// stop or detach.
+pub struct EventLoop {
+    process: Wrapped<bindings::SBProcess>,
+    listener: Wrapped<bindings::SBListener>,
+    timeout: Option<Duration>,
+    exit_code: Option<i32>,
+    handlers: StopHandlers,
+    module_load: Option<ModuleHandler>,
+    stdout: Option<OutputHandler>,
+    exit: Option<ExitHandler>,
+}
+
+impl EventLoop {
+    /// Create a loop for the process, subscribing a dedicated listener to the process and target.
+    pub fn new(process: Wrapped<bindings::SBProcess>) -> EventLoop {
+        let mut listener = Wrapped::<bindings::SBListener>::with_name("lldb_rs.event_loop");
+        listener.start_listening_for_events(
+            &process.broadcaster(),
+            ProcessEvents::STATE_CHANGED | ProcessEvents::STDOUT,
+        );
+        listener.start_listening_for_events(
+            &process.target().broadcaster(),
+            TargetEvents::MODULES_LOADED,
+        );
+        EventLoop {
+            process,
+            listener,
+            timeout: None,
+            exit_code: None,
+            handlers: StopHandlers::new(),
+            module_load: None,
+            stdout: None,
+            exit: None,
+        }
+    }
+
+    /// Fail with a timeout if no event arrives within this duration, by default it waits forever.
+    /// LLDB waits in whole seconds, so the timeout is rounded up to whole seconds, and a zero
+    /// timeout only takes events that are already queued.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// What to do on stops no handler is registered for, like an unknown breakpoint. Defaults to
+    /// stopping.
+    pub fn unhandled(&mut self, flow: Flow) -> &mut Self {
+        self.handlers.unhandled = flow;
+        self
+    }
+
+    pub fn on_breakpoint<F: FnMut(&mut Stop) -> Flow + 'static>(
+        &mut self,
+        id: BreakpointId,
+        f: F,
+    ) -> &mut Self {
+        self.handlers.breakpoints.insert(id, Box::new(f));
+        self
+    }
+
+    pub fn on_watchpoint<F: FnMut(&mut Stop) -> Flow + 'static>(
+        &mut self,
+        id: WatchpointId,
+        f: F,
+    ) -> &mut Self {
+        self.handlers.watchpoints.insert(id, Box::new(f));
+        self
+    }
+
+    /// Called with the signal number when a thread stops for a signal.
+    pub fn on_signal<F: FnMut(&mut Stop, i32) -> Flow + 'static>(&mut self, f: F) -> &mut Self {
+        self.handlers.signal = Some(Box::new(f));
+        self
+    }
+
+    /// Called with the stop description when a thread stops for an exception.
+    pub fn on_exception<F: FnMut(&mut Stop, &str) -> Flow + 'static>(&mut self, f: F) -> &mut Self {
+        self.handlers.exception = Some(Box::new(f));
+        self
+    }
+
+    /// Called with the paths of newly loaded modules.
+    pub fn on_module_load<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut(&mut Wrapped<bindings::SBProcess>, &[String]) -> Flow + 'static,
+    {
+        self.module_load = Some(Box::new(f));
+        self
+    }
+
+    /// Called with output of the process, only for processes launched with their stdout going to
+    /// LLDB.
+    pub fn on_stdout<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut(&mut Wrapped<bindings::SBProcess>, &[u8]) -> Flow + 'static,
+    {
+        self.stdout = Some(Box::new(f));
+        self
+    }
+
+    /// Called with the exit code once the process exited.
+    pub fn on_exit<F: FnMut(i32) + 'static>(&mut self, f: F) -> &mut Self {
+        self.exit = Some(Box::new(f));
+        self
+    }
+
+    pub fn process(&mut self) -> &mut Wrapped<bindings::SBProcess> {
+        &mut self.process
+    }
+
+    /// The exit code, once the process exited.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Resume the process if it is stopped and dispatch events until it exits, detaches or a
+    /// handler asks to stop.
+    pub fn run(&mut self) -> Result<Outcome, EventLoopError> {
+        match self.process.get_state() {
+            bindings::StateType::eStateExited => return Ok(self.exited()),
+            bindings::StateType::eStateDetached => return Ok(Outcome::Detached),
+            bindings::StateType::eStateStopped => self.process.resume()?,
+            _ => {}
+        }
+
+        // Set when a handler asked to stop while the process was running.
+        let mut stopping = false;
+        loop {
+            let event = self.wait()?;
+            let flow = match event.decode() {
+                DecodedEvent::ProcessStateChanged {
+                    restarted: true, ..
+                } => Flow::Continue,
+                DecodedEvent::ProcessStateChanged { state, .. } => match state {
+                    bindings::StateType::eStateExited => return Ok(self.exited()),
+                    bindings::StateType::eStateDetached => return Ok(Outcome::Detached),
+                    bindings::StateType::eStateStopped | bindings::StateType::eStateCrashed => {
+                        if stopping {
+                            return Ok(Outcome::Stopped);
+                        }
+                        let flow = self.dispatch_stop();
+                        if flow == Flow::Detach {
+                            self.process.detach()?;
+                        }
+                        match stop_outcome(flow) {
+                            Some(outcome) => return Ok(outcome),
+                            None => {
+                                self.process.resume()?;
+                                continue;
+                            }
+                        }
+                    }
+                    _ => Flow::Continue,
+                },
+                DecodedEvent::ProcessStdout => self.dispatch_stdout(),
+                DecodedEvent::TargetModulesLoaded { modules } => match &mut self.module_load {
+                    Some(handler) => handler(&mut self.process, &modules),
+                    None => Flow::Continue,
+                },
+                _ => Flow::Continue,
+            };
+
+            match flow {
+                Flow::Continue => {}
+                Flow::Stop => {
+                    if !stopping {
+                        stopping = true;
+                        self.process.stop()?;
+                    }
+                }
+                Flow::Detach => {
+                    self.process.detach()?;
+                    return Ok(Outcome::Detached);
+                }
+            }
+        }
+    }
+
+    fn wait(&mut self) -> Result<Wrapped<bindings::SBEvent>, EventLoopError> {
+        match self.timeout {
+            Some(timeout) => self
+                .listener
+                .wait_for_event(timeout)
+                .ok_or(EventLoopError::Timeout),
+            None => loop {
+                if let Some(event) = self.listener.wait_for_event(Duration::from_secs(60)) {
+                    return Ok(event);
+                }
+            },
+        }
+    }
+
+    fn exited(&mut self) -> Outcome {
+        let code = self.process.get_exit_status();
+        self.exit_code = Some(code);
+        if let Some(handler) = &mut self.exit {
+            handler(code);
+        }
+        Outcome::Exited(code)
+    }
+
+    /// Run the handlers for all threads with a stop reason and combine their results.
+    fn dispatch_stop(&mut self) -> Flow {
+        let mut result = None;
+        for mut thread in self.process.threads() {
+            let cause = StopCause::of(&mut thread);
+            let mut stop = Stop {
+                process: &mut self.process,
+                thread: &mut thread,
+            };
+            result = result.max(self.handlers.dispatch(&mut stop, &cause));
+        }
+        // Stopped without any thread having a reason, like an interrupt from elsewhere.
+        result.unwrap_or(self.handlers.unhandled)
+    }
+
+    fn dispatch_stdout(&mut self) -> Flow {
+        let handler = match &mut self.stdout {
+            Some(handler) => handler,
+            None => return Flow::Continue,
+        };
+        let mut output = vec![];
+        let mut buffer = [0u8; 4096];
+        loop {
+            let len = self.process.get_stdout(&mut buffer);
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..len]);
+        }
+        if output.is_empty() {
+            return Flow::Continue;
+        }
+        handler(&mut self.process, &output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flow_precedence() {
+        let flows = [Flow::Continue, Flow::Detach, Flow::Stop];
+        assert_eq!(flows.iter().max(), Some(&Flow::Detach));
+        assert_eq!(Some(Flow::Continue).max(None), Some(Flow::Continue));
+        assert!(Flow::Stop > Flow::Continue);
+    }
+
+    #[test]
+    fn test_dispatch() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut process = bindings::SBProcess::new().wrap();
+        let mut thread = bindings::SBThread::new().wrap();
+        assert_eq!(StopCause::of(&mut thread), StopCause::Halted);
+        let mut stop = Stop {
+            process: &mut process,
+            thread: &mut thread,
+        };
+
+        let seen = Rc::new(RefCell::new(vec![]));
+        let mut handlers = StopHandlers::new();
+        let s = seen.clone();
+        handlers.breakpoints.insert(
+            BreakpointId(1),
+            Box::new(move |_: &mut Stop| {
+                s.borrow_mut().push("bp1");
+                Flow::Continue
+            }),
+        );
+        let s = seen.clone();
+        handlers.breakpoints.insert(
+            BreakpointId(2),
+            Box::new(move |_: &mut Stop| {
+                s.borrow_mut().push("bp2");
+                Flow::Detach
+            }),
+        );
+        let s = seen.clone();
+        handlers.signal = Some(Box::new(move |_: &mut Stop, signal| {
+            assert_eq!(signal, 11);
+            s.borrow_mut().push("signal");
+            Flow::Stop
+        }));
+
+        let cause = StopCause::Breakpoints(vec![BreakpointId(1)]);
+        assert_eq!(handlers.dispatch(&mut stop, &cause), Some(Flow::Continue));
+        // Multiple breakpoints at the same address, the strongest flow wins.
+        let cause = StopCause::Breakpoints(vec![BreakpointId(2), BreakpointId(1)]);
+        assert_eq!(handlers.dispatch(&mut stop, &cause), Some(Flow::Detach));
+        assert_eq!(
+            handlers.dispatch(&mut stop, &StopCause::Signal(11)),
+            Some(Flow::Stop)
+        );
+        assert_eq!(*seen.borrow(), ["bp1", "bp2", "bp1", "signal"]);
+
+        // Stops without a handler use the unhandled flow, halted threads are skipped.
+        handlers.unhandled = Flow::Continue;
+        let cause = StopCause::Breakpoints(vec![BreakpointId(3)]);
+        assert_eq!(handlers.dispatch(&mut stop, &cause), Some(Flow::Continue));
+        let cause = StopCause::Watchpoint(Some(WatchpointId(1)));
+        assert_eq!(handlers.dispatch(&mut stop, &cause), Some(Flow::Continue));
+        let cause = StopCause::Exception("access violation".to_string());
+        assert_eq!(handlers.dispatch(&mut stop, &cause), Some(Flow::Continue));
+        assert_eq!(handlers.dispatch(&mut stop, &StopCause::Halted), None);
+        assert_eq!(seen.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_stop_outcome() {
+        assert_eq!(stop_outcome(Flow::Continue), None);
+        assert_eq!(stop_outcome(Flow::Stop), Some(Outcome::Stopped));
+        assert_eq!(stop_outcome(Flow::Detach), Some(Outcome::Detached));
+    }
+
+    #[test]
+    fn test_run_without_process() {
+        bindings::SBDebugger::Initialize();
+        let mut event_loop = EventLoop::new(bindings::SBProcess::new().wrap());
+        event_loop.timeout(Duration::ZERO);
+        assert_eq!(event_loop.run(), Err(EventLoopError::Timeout));
+        assert_eq!(event_loop.exit_code(), None);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+
+// The event type is a bitmask whose meaning depends on the class of the broadcaster that sent it,
+// these are anonymous enums in the SB classes which are not generated, so they're mirrored here.
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBProcess.h
+
+bitflags::bitflags! {
+    /// Event bits broadcast by a process.
+    pub struct ProcessEvents: u32 {
+        const STATE_CHANGED = 1 << 0;
+        const INTERRUPT = 1 << 1;
+        const STDOUT = 1 << 2;
+        const STDERR = 1 << 3;
+        const PROFILE_DATA = 1 << 4;
+        const STRUCTURED_DATA = 1 << 5;
+    }
+}
+
+bitflags::bitflags! {
+    /// Event bits broadcast by a target, breakpoint and watchpoint events also originate here.
+    pub struct TargetEvents: u32 {
+        const BREAKPOINT_CHANGED = 1 << 0;
+        const MODULES_LOADED = 1 << 1;
+        const MODULES_UNLOADED = 1 << 2;
+        const WATCHPOINT_CHANGED = 1 << 3;
+        const SYMBOLS_LOADED = 1 << 4;
+    }
+}
+
+bitflags::bitflags! {
+    /// Event bits broadcast by a thread.
+    pub struct ThreadEvents: u32 {
+        const STACK_CHANGED = 1 << 0;
+        const THREAD_SUSPENDED = 1 << 1;
+        const THREAD_RESUMED = 1 << 2;
+        const SELECTED_FRAME_CHANGED = 1 << 3;
+        const THREAD_SELECTED = 1 << 4;
+    }
+}
+
+bitflags::bitflags! {
+    /// Event bits broadcast by the command interpreter.
+    pub struct CommandInterpreterEvents: u32 {
+        const THREAD_SHOULD_EXIT = 1 << 0;
+        const RESET_PROMPT = 1 << 1;
+        const QUIT_COMMAND_RECEIVED = 1 << 2;
+        const ASYNCHRONOUS_OUTPUT_DATA = 1 << 3;
+        const ASYNCHRONOUS_ERROR_DATA = 1 << 4;
+    }
+}
+
+/// Typed event bits for a broadcaster class.
+pub trait EventMask: Copy {
+    fn to_bits(&self) -> u32;
+    fn from_bits_lossy(bits: u32) -> Self;
+}
+
+macro_rules! impl_event_mask {
+    ($($t:ty),*) => {
+        $(
+            impl EventMask for $t {
+                fn to_bits(&self) -> u32 {
+                    self.bits()
+                }
+                fn from_bits_lossy(bits: u32) -> Self {
+                    <$t>::from_bits_truncate(bits)
+                }
+            }
+        )*
+    };
+}
+impl_event_mask!(
+    ProcessEvents,
+    TargetEvents,
+    ThreadEvents,
+    CommandInterpreterEvents
+);
+
+/// An event decoded into plain data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedEvent {
+    ProcessStateChanged {
+        state: bindings::StateType,
+        /// The process stopped, but was automatically restarted.
+        restarted: bool,
+    },
+    /// Output is available, read it through the process.
+    ProcessStdout,
+    ProcessStderr,
+    ProcessInterrupted,
+    BreakpointChanged {
+        kind: bindings::BreakpointEventType,
+        breakpoint: BreakpointId,
+    },
+    WatchpointChanged {
+        kind: bindings::WatchpointEventType,
+        watchpoint: WatchpointId,
+    },
+    /// Paths of the modules that were loaded.
+    TargetModulesLoaded {
+        modules: Vec<String>,
+    },
+    TargetModulesUnloaded {
+        modules: Vec<String>,
+    },
+    TargetSymbolsLoaded,
+    ThreadSelected {
+        thread_id: u64,
+    },
+    FrameChanged {
+        thread_id: u64,
+        frame_index: u32,
+    },
+    ThreadStackChanged {
+        thread_id: u64,
+    },
+    ThreadSuspended {
+        thread_id: u64,
+    },
+    ThreadResumed {
+        thread_id: u64,
+    },
+    CommandInterpreter {
+        kind: CommandInterpreterEvents,
+        data: Option<String>,
+    },
+    /// Anything we don't know how to decode.
+    Unknown {
+        broadcaster_class: String,
+        event_type: u32,
+    },
+}
+
+fn modules_from_event(event: &bindings::SBEvent) -> Vec<String> {
+    (0..bindings::SBTarget::GetNumModulesFromEvent(event))
+        .map(|i| {
+            bindings::SBTarget::GetModuleAtIndexFromEvent(i, event)
+                .wrap()
+                .get_file_spec()
+                .get_path()
+        })
+        .collect()
+}
+
+/// Decode an event, using the `EventIsXEvent` helpers to determine the broadcaster class.
+pub fn decode(event: &bindings::SBEvent) -> DecodedEvent {
+    let event_type = event.GetType();
+
+    if bindings::SBProcess::EventIsProcessEvent(event) {
+        let bits = ProcessEvents::from_bits_truncate(event_type);
+        if bits.contains(ProcessEvents::STATE_CHANGED) {
+            return DecodedEvent::ProcessStateChanged {
+                state: bindings::SBProcess::GetStateFromEvent(event),
+                restarted: bindings::SBProcess::GetRestartedFromEvent(event),
+            };
+        }
+        if bits.contains(ProcessEvents::STDOUT) {
+            return DecodedEvent::ProcessStdout;
+        }
+        if bits.contains(ProcessEvents::STDERR) {
+            return DecodedEvent::ProcessStderr;
+        }
+        if bits.contains(ProcessEvents::INTERRUPT) {
+            return DecodedEvent::ProcessInterrupted;
+        }
+    } else if bindings::SBBreakpoint::EventIsBreakpointEvent(event) {
+        let bp = bindings::SBBreakpoint::GetBreakpointFromEvent(event).wrap();
+        return DecodedEvent::BreakpointChanged {
+            kind: bindings::SBBreakpoint::GetBreakpointEventTypeFromEvent(event),
+            breakpoint: bp.get_id(),
+        };
+    } else if bindings::SBWatchpoint::EventIsWatchpointEvent(event) {
+        let mut wp = bindings::SBWatchpoint::GetWatchpointFromEvent(event).wrap();
+        return DecodedEvent::WatchpointChanged {
+            kind: bindings::SBWatchpoint::GetWatchpointEventTypeFromEvent(event),
+            watchpoint: wp.get_id(),
+        };
+    } else if bindings::SBTarget::EventIsTargetEvent(event) {
+        let bits = TargetEvents::from_bits_truncate(event_type);
+        if bits.contains(TargetEvents::MODULES_LOADED) {
+            return DecodedEvent::TargetModulesLoaded {
+                modules: modules_from_event(event),
+            };
+        }
+        if bits.contains(TargetEvents::MODULES_UNLOADED) {
+            return DecodedEvent::TargetModulesUnloaded {
+                modules: modules_from_event(event),
+            };
+        }
+        if bits.contains(TargetEvents::SYMBOLS_LOADED) {
+            return DecodedEvent::TargetSymbolsLoaded;
+        }
+    } else if bindings::SBThread::EventIsThreadEvent(event) {
+        let bits = ThreadEvents::from_bits_truncate(event_type);
+        let thread_id = bindings::SBThread::GetThreadFromEvent(event)
+            .wrap()
+            .get_thread_id();
+        if bits.contains(ThreadEvents::THREAD_SELECTED) {
+            return DecodedEvent::ThreadSelected { thread_id };
+        }
+        if bits.contains(ThreadEvents::SELECTED_FRAME_CHANGED) {
+            let frame = bindings::SBThread::GetStackFrameFromEvent(event).wrap();
+            return DecodedEvent::FrameChanged {
+                thread_id,
+                frame_index: frame.as_ref().GetFrameID(),
+            };
+        }
+        if bits.contains(ThreadEvents::STACK_CHANGED) {
+            return DecodedEvent::ThreadStackChanged { thread_id };
+        }
+        if bits.contains(ThreadEvents::THREAD_SUSPENDED) {
+            return DecodedEvent::ThreadSuspended { thread_id };
+        }
+        if bits.contains(ThreadEvents::THREAD_RESUMED) {
+            return DecodedEvent::ThreadResumed { thread_id };
+        }
+    } else if bindings::SBCommandInterpreter::EventIsCommandInterpreterEvent(event) {
+        return DecodedEvent::CommandInterpreter {
+            kind: CommandInterpreterEvents::from_bits_truncate(event_type),
+            data: to_string_opt(bindings::SBEvent::GetCStringFromEvent(event)),
+        };
+    }
+
+    DecodedEvent::Unknown {
+        broadcaster_class: to_string_opt(event.GetBroadcasterClass()).unwrap_or_default(),
+        event_type,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_default() {
+        let event = bindings::SBEvent::new().wrap();
+        assert!(matches!(
+            decode(event.as_ref()),
+            DecodedEvent::Unknown { event_type: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_event_mask_bits() {
+        assert_eq!(ProcessEvents::STATE_CHANGED.to_bits(), 0x1);
+        assert_eq!(
+            (ProcessEvents::STDOUT | ProcessEvents::STDERR).to_bits(),
+            0xc
+        );
+        assert_eq!(TargetEvents::MODULES_LOADED.to_bits(), 0x2);
+        assert_eq!(TargetEvents::SYMBOLS_LOADED.to_bits(), 0x10);
+        assert_eq!(ThreadEvents::THREAD_SELECTED.to_bits(), 0x10);
+        assert_eq!(
+            CommandInterpreterEvents::ASYNCHRONOUS_OUTPUT_DATA.to_bits(),
+            0x8
+        );
+        // Unknown bits are dropped.
+        assert_eq!(
+            ProcessEvents::from_bits_lossy(0x1 | 0x100),
+            ProcessEvents::STATE_CHANGED
+        );
+        assert_eq!(
+            TargetEvents::from_bits_lossy(0x6),
+            TargetEvents::MODULES_LOADED | TargetEvents::MODULES_UNLOADED
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+use autocxx::PinMut;
+use std::time::Duration;
+
+// The outcome of an expression is reported through the error of the resulting value, its code is
+// one of the ExpressionResults and its string holds the diagnostics of the compiler. Expressions
+// without a result, like calls to void functions, also carry an error with a dedicated code.
+
+/// The error code of the result of an expression that has no result, like `(void)0`.
+const NO_RESULT: u32 = 0x1001;
+
+/// Options for expression evaluation, the defaults match those of LLDB.
+#[derive(Debug, Clone)]
+pub struct ExpressionOptions {
+    timeout: Option<Duration>,
+    language: Option<bindings::LanguageType>,
+    unwind_on_error: bool,
+    ignore_breakpoints: bool,
+    try_all_threads: bool,
+    fetch_dynamic_value: bindings::DynamicValueType,
+    top_level: bool,
+}
+
+impl Default for ExpressionOptions {
+    fn default() -> Self {
+        ExpressionOptions {
+            timeout: None,
+            language: None,
+            unwind_on_error: true,
+            ignore_breakpoints: true,
+            try_all_threads: true,
+            fetch_dynamic_value: bindings::DynamicValueType::eNoDynamicValues,
+            top_level: false,
+        }
+    }
+}
+
+impl ExpressionOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Give up on the expression after this long, LLDB's default applies if not set.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Language to parse the expression in, the language of the frame if not set.
+    pub fn language(mut self, language: bindings::LanguageType) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Restore the thread state if the expression crashes or otherwise fails.
+    pub fn unwind_on_error(mut self, unwind: bool) -> Self {
+        self.unwind_on_error = unwind;
+        self
+    }
+
+    pub fn ignore_breakpoints(mut self, ignore: bool) -> Self {
+        self.ignore_breakpoints = ignore;
+        self
+    }
+
+    /// Let other threads run if the expression doesn't complete on the current thread in time,
+    /// avoids deadlocks on locks held by other threads.
+    pub fn try_all_threads(mut self, try_all: bool) -> Self {
+        self.try_all_threads = try_all;
+        self
+    }
+
+    pub fn fetch_dynamic_value(mut self, dynamic: bindings::DynamicValueType) -> Self {
+        self.fetch_dynamic_value = dynamic;
+        self
+    }
+
+    /// Evaluate as top level code, for declaring functions and types used by later expressions.
+    pub fn top_level(mut self, top_level: bool) -> Self {
+        self.top_level = top_level;
+        self
+    }
+
+    pub(crate) fn to_sb(&self) -> Wrapped<bindings::SBExpressionOptions> {
+        let mut res = bindings::SBExpressionOptions::new().wrap();
+        if let Some(timeout) = self.timeout {
+            let micros = u32::try_from(timeout.as_micros()).unwrap_or(u32::MAX);
+            res.pin_mut().SetTimeoutInMicroSeconds(micros);
+        }
+        if let Some(language) = &self.language {
+            res.pin_mut().SetLanguage(language.clone());
+        }
+        res.pin_mut().SetUnwindOnError(self.unwind_on_error);
+        res.pin_mut().SetIgnoreBreakpoints(self.ignore_breakpoints);
+        res.pin_mut().SetTryAllThreads(self.try_all_threads);
+        res.pin_mut()
+            .SetFetchDynamicValue(self.fetch_dynamic_value.clone());
+        res.pin_mut().SetTopLevel(self.top_level);
+        res
+    }
+}
+
+/// Why an expression failed, mirrors `lldb::ExpressionResults`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExpressionErrorKind {
+    Setup,
+    /// The expression didn't compile, the diagnostics say why.
+    Parse,
+    Discarded,
+    Interrupted,
+    HitBreakpoint,
+    TimedOut,
+    ResultUnavailable,
+    StoppedForDebug,
+    ThreadVanished,
+    /// Any other error code.
+    Other(u32),
+}
+
+impl ExpressionErrorKind {
+    pub fn from_code(code: u32) -> ExpressionErrorKind {
+        match code {
+            1 => ExpressionErrorKind::Setup,
+            2 => ExpressionErrorKind::Parse,
+            3 => ExpressionErrorKind::Discarded,
+            4 => ExpressionErrorKind::Interrupted,
+            5 => ExpressionErrorKind::HitBreakpoint,
+            6 => ExpressionErrorKind::TimedOut,
+            7 => ExpressionErrorKind::ResultUnavailable,
+            8 => ExpressionErrorKind::StoppedForDebug,
+            9 => ExpressionErrorKind::ThreadVanished,
+            c => ExpressionErrorKind::Other(c),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    pub kind: ExpressionErrorKind,
+    /// The full error text from LLDB.
+    pub message: String,
+}
+
+impl ExpressionError {
+    /// The individual compiler diagnostics, one per line of the message.
+    pub fn diagnostics(&self) -> Vec<&str> {
+        self.message
+            .lines()
+            .map(|l| l.trim_end())
+            .filter(|l| !l.is_empty())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "expression failed ({:?}): {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// Turn the result of an expression into an error if the expression failed.
+pub(crate) fn check_result(
+    mut value: Wrapped<bindings::SBValue>,
+) -> Result<Wrapped<bindings::SBValue>, ExpressionError> {
+    if !value.pin_mut().IsValid() {
+        return Err(ExpressionError {
+            kind: ExpressionErrorKind::ResultUnavailable,
+            message: "no result value".to_string(),
+        });
+    }
+    let e = value.pin_mut().GetError().wrap();
+    if e.is_success() || e.get_error_code() == NO_RESULT {
+        return Ok(value);
+    }
+    Err(ExpressionError {
+        kind: ExpressionErrorKind::from_code(e.get_error_code()),
+        message: e.get_str().trim_end().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error() {
+        let e = ExpressionError {
+            kind: ExpressionErrorKind::from_code(2),
+            message: "error: <user expression 0>:1:1: use of undeclared identifier 'foo'\n\
+                      error: <user expression 0>:1:5: expected ';'\n"
+                .to_string(),
+        };
+        assert_eq!(e.kind, ExpressionErrorKind::Parse);
+        assert_eq!(e.diagnostics().len(), 2);
+        assert_eq!(
+            ExpressionErrorKind::from_code(42),
+            ExpressionErrorKind::Other(42)
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options = ExpressionOptions::new()
+            .timeout(Duration::from_millis(250))
+            .ignore_breakpoints(false)
+            .top_level(true);
+        let mut sb = options.to_sb();
+        assert_eq!(sb.as_ref().GetTimeoutInMicroSeconds(), 250_000);
+        assert!(!sb.as_ref().GetIgnoreBreakpoints());
+        assert!(sb.pin_mut().GetTopLevel());
+        assert!(sb.as_ref().GetUnwindOnError());
+    }
+
+    #[test]
+    fn test_check_result() {
+        let e = check_result(bindings::SBValue::new().wrap()).expect_err("invalid value");
+        assert_eq!(e.kind, ExpressionErrorKind::ResultUnavailable);
+    }
+}
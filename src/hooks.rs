@@ -0,0 +1,583 @@
+use crate::api::ffi::lldb as bindings;
+use crate::calling_convention::{read_pointer, CallingConvention, Convention};
+use crate::wrappers::*;
+use std::any::Any;
+use std::collections::HashMap;
+
+// A function hook places a breakpoint on the entry of a function, when that is hit the return
+// address is read and a one shot breakpoint is placed there, restricted to the thread that made
+// the call. Calls are matched to returns per thread using the stack pointer, such that recursion
+// and multiple in-flight calls work.
+
+pub type HookResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+type EntryCallback<S> = Box<dyn FnMut(&CallInfo, &mut Wrapped<bindings::SBFrame>) -> HookResult<S>>;
+type ReturnCallback<S> =
+    Box<dyn FnMut(&CallInfo, S, &mut Wrapped<bindings::SBFrame>) -> HookResult<()>>;
+
+/// Where a hook is placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookLocation {
+    /// Resolved by name in all modules, including those loaded later.
+    Symbol(String),
+    /// Absolute load address.
+    Address(u64),
+    /// Offset from the image base of a module, resolved relative to the module such that it
+    /// follows the module wherever it is loaded. Until the module is loaded the hook is pending,
+    /// see [`FunctionHooks::resolve_pending`].
+    ModuleOffset { module: String, offset: u64 },
+}
+
+/// Parses `0x6ff6cd50` as an address, `module.dll+0x1d50` as a module offset and anything else as
+/// a symbol name. Offsets are hexadecimal with a `0x` prefix, decimal otherwise. If what follows
+/// the last `+` isn't a number it is part of a symbol, like `operator+=`.
+impl std::str::FromStr for HookLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty hook location".to_string());
+        }
+        let split = s
+            .rsplit_once('+')
+            .and_then(|(m, o)| Some((m.trim(), parse_number(o.trim())?)));
+        if let Some((module, offset)) = split {
+            if module.is_empty() {
+                return Err(format!("missing module in {s}"));
+            }
+            return Ok(HookLocation::ModuleOffset {
+                module: module.to_string(),
+                offset,
+            });
+        }
+        if s.starts_with("0x") {
+            return Ok(HookLocation::Address(
+                parse_number(s).ok_or_else(|| format!("invalid address {s}"))?,
+            ));
+        }
+        Ok(HookLocation::Symbol(s.to_string()))
+    }
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// Information about a single call of a hooked function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInfo {
+    pub hook: HookId,
+    pub thread_id: u64,
+    /// Number of in-flight calls of this hook on this thread, 0 for the outermost call.
+    pub depth: usize,
+    pub entry_pc: u64,
+    pub return_address: u64,
+    /// Stack pointer at the function entry.
+    pub stack_pointer: u64,
+}
+
+/// A hook on a function, the state returned by `on_entry` is passed to `on_return`.
+pub struct FunctionHook<S = ()> {
+    location: HookLocation,
+    on_entry: EntryCallback<S>,
+    on_return: Option<ReturnCallback<S>>,
+}
+
+impl FunctionHook<()> {
+    /// Hook a function by its symbol name.
+    pub fn by_name(name: &str) -> FunctionHook<()> {
+        FunctionHook::new(HookLocation::Symbol(name.to_string()))
+    }
+
+    /// Hook a function by its load address.
+    pub fn by_address(address: u64) -> FunctionHook<()> {
+        FunctionHook::new(HookLocation::Address(address))
+    }
+
+    /// Hook a function by its offset from the image base of a module, like `module.dll+0x1d50`.
+    pub fn by_module_offset(module: &str, offset: u64) -> FunctionHook<()> {
+        FunctionHook::new(HookLocation::ModuleOffset {
+            module: module.to_string(),
+            offset,
+        })
+    }
+
+    pub fn new(location: HookLocation) -> FunctionHook<()> {
+        FunctionHook {
+            location,
+            on_entry: Box::new(|_, _| Ok(())),
+            on_return: None,
+        }
+    }
+}
+
+impl<S: 'static> FunctionHook<S> {
+    /// Set the entry callback, this must be called before [`FunctionHook::on_return`] as it
+    /// determines the state passed to the return callback.
+    pub fn on_entry<N, F>(self, f: F) -> FunctionHook<N>
+    where
+        F: FnMut(&CallInfo, &mut Wrapped<bindings::SBFrame>) -> HookResult<N> + 'static,
+    {
+        FunctionHook {
+            location: self.location,
+            on_entry: Box::new(f),
+            on_return: None,
+        }
+    }
+
+    /// Set the return callback, return breakpoints are only placed if this is set.
+    pub fn on_return<F>(mut self, f: F) -> FunctionHook<S>
+    where
+        F: FnMut(&CallInfo, S, &mut Wrapped<bindings::SBFrame>) -> HookResult<()> + 'static,
+    {
+        self.on_return = Some(Box::new(f));
+        self
+    }
+
+    pub fn location(&self) -> &HookLocation {
+        &self.location
+    }
+}
+
+/// Type erased hook, such that hooks with different states can live in one collection.
+trait ErasedHook {
+    fn has_return(&self) -> bool;
+    fn entry(
+        &mut self,
+        call: &CallInfo,
+        frame: &mut Wrapped<bindings::SBFrame>,
+    ) -> HookResult<Box<dyn Any>>;
+    fn exit(
+        &mut self,
+        call: &CallInfo,
+        state: Box<dyn Any>,
+        frame: &mut Wrapped<bindings::SBFrame>,
+    ) -> HookResult<()>;
+}
+
+impl<S: 'static> ErasedHook for FunctionHook<S> {
+    fn has_return(&self) -> bool {
+        self.on_return.is_some()
+    }
+    fn entry(
+        &mut self,
+        call: &CallInfo,
+        frame: &mut Wrapped<bindings::SBFrame>,
+    ) -> HookResult<Box<dyn Any>> {
+        Ok(Box::new((self.on_entry)(call, frame)?))
+    }
+    fn exit(
+        &mut self,
+        call: &CallInfo,
+        state: Box<dyn Any>,
+        frame: &mut Wrapped<bindings::SBFrame>,
+    ) -> HookResult<()> {
+        let state = *state
+            .downcast::<S>()
+            .expect("state type always matches the hook");
+        if let Some(on_return) = self.on_return.as_mut() {
+            (on_return)(call, state, frame)?;
+        }
+        Ok(())
+    }
+}
+
+struct RegisteredHook {
+    hook: Box<dyn ErasedHook>,
+    location: HookLocation,
+    /// None while the hook is pending on a module that isn't loaded yet.
+    breakpoint: Option<BreakpointId>,
+}
+
+struct PendingCall {
+    info: CallInfo,
+    state: Box<dyn Any>,
+}
+
+/// Collection of function hooks, feed it the threads of a stopped process through
+/// [`FunctionHooks::handle_stop`].
+#[derive(Default)]
+pub struct FunctionHooks {
+    hooks: HashMap<HookId, RegisteredHook>,
+    entries: HashMap<BreakpointId, HookId>,
+    /// Return breakpoints, with the thread and return address they belong to.
+    returns: HashMap<BreakpointId, (u64, u64)>,
+    /// In-flight calls per thread, in order of entry.
+    in_flight: HashMap<u64, Vec<PendingCall>>,
+    next_id: u64,
+}
+
+impl FunctionHooks {
+    pub fn new() -> FunctionHooks {
+        Default::default()
+    }
+
+    /// Register a hook, this creates the entry breakpoint. A hook on an offset in a module that
+    /// isn't loaded yet is kept pending until [`FunctionHooks::resolve_pending`] finds the module.
+    pub fn register<T: Target, S: 'static>(
+        &mut self,
+        target: &mut T,
+        hook: FunctionHook<S>,
+    ) -> HookResult<HookId> {
+        let location = hook.location().clone();
+        let breakpoint = create_breakpoint(target, &location)?;
+        let id = HookId(self.next_id);
+        self.next_id += 1;
+        if let Some(bp) = breakpoint {
+            self.entries.insert(bp, id);
+        }
+        self.hooks.insert(
+            id,
+            RegisteredHook {
+                hook: Box::new(hook),
+                location,
+                breakpoint,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Create the entry breakpoints of pending hooks whose module got loaded, call this when the
+    /// target reports [`crate::events::DecodedEvent::TargetModulesLoaded`]. Returns the hooks that
+    /// got placed.
+    pub fn resolve_pending<T: Target>(&mut self, target: &mut T) -> HookResult<Vec<HookId>> {
+        let mut resolved = vec![];
+        for (id, registered) in self.hooks.iter_mut() {
+            if registered.breakpoint.is_some() {
+                continue;
+            }
+            if let Some(bp) = create_breakpoint(target, &registered.location)? {
+                registered.breakpoint = Some(bp);
+                self.entries.insert(bp, *id);
+                resolved.push(*id);
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Whether the hook waits for its module to be loaded.
+    pub fn is_pending(&self, id: HookId) -> bool {
+        self.hooks
+            .get(&id)
+            .map(|r| r.breakpoint.is_none())
+            .unwrap_or(false)
+    }
+
+    /// Remove a hook, its in-flight calls are dropped without calling the return callback.
+    pub fn unregister<T: Target>(&mut self, target: &mut T, id: HookId) -> bool {
+        let registered = match self.hooks.remove(&id) {
+            Some(v) => v,
+            None => return false,
+        };
+        if let Some(bp) = registered.breakpoint {
+            self.entries.remove(&bp);
+            target.delete_breakpoint(bp);
+        }
+        for calls in self.in_flight.values_mut() {
+            calls.retain(|c| c.info.hook != id);
+        }
+        self.remove_unused_returns(target);
+        true
+    }
+
+    /// Number of in-flight calls on a thread.
+    pub fn in_flight(&self, thread_id: u64) -> usize {
+        self.in_flight.get(&thread_id).map(|v| v.len()).unwrap_or(0)
+    }
+
+    /// Handle a stop of this thread, returns whether the stop was caused by one of the hooks.
+    /// Resuming the process is left to the caller.
+    pub fn handle_stop<T: Target, Th: Thread>(
+        &mut self,
+        target: &mut T,
+        thread: &mut Th,
+    ) -> HookResult<bool> {
+        let mut handled = false;
+        for bp in thread.stop_breakpoint_ids() {
+            if let Some(hook) = self.entries.get(&bp).copied() {
+                self.handle_entry(target, thread, hook)?;
+                handled = true;
+            } else if let Some((thread_id, return_address)) = self.returns.remove(&bp) {
+                self.handle_return(target, thread, thread_id, return_address)?;
+                handled = true;
+            }
+        }
+        Ok(handled)
+    }
+
+    fn handle_entry<T: Target, Th: Thread>(
+        &mut self,
+        target: &mut T,
+        thread: &mut Th,
+        hook: HookId,
+    ) -> HookResult<()> {
+        let thread_id = thread.get_thread_id();
+        let mut frame = thread.frame(0);
+        let return_address = read_return_address(target, &mut frame)?;
+        let pending = self.in_flight.entry(thread_id).or_default();
+        let info = CallInfo {
+            hook,
+            thread_id,
+            depth: pending.iter().filter(|c| c.info.hook == hook).count(),
+            entry_pc: frame.get_pc(),
+            return_address,
+            stack_pointer: frame.get_sp(),
+        };
+
+        let registered = self.hooks.get_mut(&hook).expect("entry for unknown hook");
+        let state = registered.hook.entry(&info, &mut frame)?;
+        if !registered.hook.has_return() {
+            return Ok(());
+        }
+        pending.push(PendingCall { info, state });
+        self.ensure_return_breakpoint(target, thread_id, return_address);
+        Ok(())
+    }
+
+    fn handle_return<T: Target, Th: Thread>(
+        &mut self,
+        target: &mut T,
+        thread: &mut Th,
+        thread_id: u64,
+        return_address: u64,
+    ) -> HookResult<()> {
+        let mut frame = thread.frame(0);
+        let sp = frame.get_sp();
+        let pending = self.in_flight.entry(thread_id).or_default();
+
+        // The call that returned is the innermost one with this return address whose entry stack
+        // pointer is at or below the current one.
+        let index = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.info.return_address == return_address && c.info.stack_pointer <= sp)
+            .max_by_key(|(_, c)| c.info.stack_pointer)
+            .map(|(i, _)| i);
+
+        if let Some(index) = index {
+            let call = pending.remove(index);
+            // Anything deeper on the stack than this call can no longer return.
+            pending.retain(|c| c.info.stack_pointer >= call.info.stack_pointer);
+            if let Some(registered) = self.hooks.get_mut(&call.info.hook) {
+                registered.hook.exit(&call.info, call.state, &mut frame)?;
+            }
+        }
+
+        // Other calls may still be waiting for this return address, like with recursion.
+        let still_waiting = self
+            .in_flight
+            .get(&thread_id)
+            .map(|v| v.iter().any(|c| c.info.return_address == return_address))
+            .unwrap_or(false);
+        if still_waiting {
+            self.ensure_return_breakpoint(target, thread_id, return_address);
+        }
+        self.remove_unused_returns(target);
+        Ok(())
+    }
+
+    fn ensure_return_breakpoint<T: Target>(
+        &mut self,
+        target: &mut T,
+        thread_id: u64,
+        return_address: u64,
+    ) {
+        if self
+            .returns
+            .values()
+            .any(|v| *v == (thread_id, return_address))
+        {
+            return;
+        }
+        let mut bp = target.breakpoint_create_by_address(return_address);
+        bp.set_one_shot(true);
+        bp.set_thread_id(thread_id);
+        self.returns
+            .insert(bp.get_id(), (thread_id, return_address));
+    }
+
+    /// Delete return breakpoints that no in-flight call is waiting for anymore.
+    fn remove_unused_returns<T: Target>(&mut self, target: &mut T) {
+        let in_flight = &self.in_flight;
+        let unused: Vec<BreakpointId> = self
+            .returns
+            .iter()
+            .filter(|(_, (thread_id, return_address))| {
+                !in_flight
+                    .get(thread_id)
+                    .map(|v| v.iter().any(|c| c.info.return_address == *return_address))
+                    .unwrap_or(false)
+            })
+            .map(|(bp, _)| *bp)
+            .collect();
+        for bp in unused {
+            self.returns.remove(&bp);
+            target.delete_breakpoint(bp);
+        }
+    }
+}
+
+/// Create the entry breakpoint for a hook, None if it is in a module that isn't loaded yet.
+fn create_breakpoint<T: Target>(
+    target: &mut T,
+    location: &HookLocation,
+) -> HookResult<Option<BreakpointId>> {
+    let bp = match location {
+        HookLocation::Symbol(name) => target.breakpoint_create_by_name(name),
+        HookLocation::Address(address) => target.breakpoint_create_by_address(*address),
+        HookLocation::ModuleOffset { module, offset } => {
+            let mut m = match target.find_module_by_name(module) {
+                Some(m) => m,
+                None => return Ok(None),
+            };
+            let mut address =
+                Wrapped::<bindings::SBAddress>::from_module_offset(&mut m, None, *offset)?;
+            target.breakpoint_create_by_sb_address(&mut address)
+        }
+    };
+    if !bp.is_valid() {
+        return Err(format!("failed to create breakpoint for {location:?}").into());
+    }
+    Ok(Some(bp.get_id()))
+}
+
+/// Obtain the return address at function entry using the target's default calling convention.
+/// Without a known convention the return address is assumed to be at the top of the stack.
+fn read_return_address<T: Target>(
+    target: &mut T,
+    frame: &mut Wrapped<bindings::SBFrame>,
+) -> HookResult<u64> {
+    if let Some(convention) = Convention::for_target(target) {
+        return Ok(convention.return_address(frame)?);
+    }
+    let size = target.get_address_byte_size() as usize;
+    let sp = frame.get_sp();
+    Ok(read_pointer(frame, sp, size)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hook_builder() {
+        let returned = std::rc::Rc::new(std::cell::Cell::new(None));
+        let seen = returned.clone();
+        let mut hook = FunctionHook::by_name("malloc")
+            .on_entry(|call, _frame| Ok(call.entry_pc))
+            .on_return(move |_call, entry_pc: u64, _frame| {
+                seen.set(Some(entry_pc));
+                Ok(())
+            });
+        assert_eq!(hook.location(), &HookLocation::Symbol("malloc".to_string()));
+        assert!(ErasedHook::has_return(&hook));
+
+        // The state returned on entry is handed to the return callback.
+        let call = CallInfo {
+            hook: HookId(0),
+            thread_id: 1,
+            depth: 0,
+            entry_pc: 0x6ff6cd50,
+            return_address: 0x401000,
+            stack_pointer: 0x7ff000,
+        };
+        let mut frame = bindings::SBFrame::new().wrap();
+        let state = ErasedHook::entry(&mut hook, &call, &mut frame).expect("entry");
+        ErasedHook::exit(&mut hook, &call, state, &mut frame).expect("exit");
+        assert_eq!(returned.get(), Some(0x6ff6cd50));
+
+        let hook = FunctionHook::by_address(0x6ff6cd50);
+        assert!(!ErasedHook::has_return(&hook));
+        assert_eq!(FunctionHooks::new().in_flight(1), 0);
+    }
+
+    #[test]
+    fn test_pending_module_offset() {
+        bindings::SBDebugger::Initialize();
+        let mut dbg = bindings::SBDebugger::Create().wrap();
+        let mut target = dbg.create_target("").expect("empty target");
+        let exe = std::env::current_exe().expect("test executable");
+        let name = exe.file_name().and_then(|n| n.to_str()).expect("file name");
+
+        let mut hooks = FunctionHooks::new();
+        let id = hooks
+            .register(&mut target, FunctionHook::by_module_offset(name, 0x10))
+            .expect("pending hook");
+        assert!(hooks.is_pending(id));
+        assert!(hooks
+            .resolve_pending(&mut target)
+            .expect("resolve")
+            .is_empty());
+
+        // Loading the module places the breakpoint.
+        target
+            .add_module(exe.to_str().expect("utf-8 path"), None, None)
+            .expect("add module");
+        assert_eq!(
+            hooks.resolve_pending(&mut target).expect("resolve"),
+            vec![id]
+        );
+        assert!(!hooks.is_pending(id));
+        assert!(hooks
+            .resolve_pending(&mut target)
+            .expect("resolve")
+            .is_empty());
+        assert!(hooks.unregister(&mut target, id));
+
+        // Pending hooks can be removed before they're placed.
+        let id = hooks
+            .register(
+                &mut target,
+                FunctionHook::by_module_offset("absent.dll", 0x10),
+            )
+            .expect("pending hook");
+        assert!(hooks.is_pending(id));
+        assert!(hooks.unregister(&mut target, id));
+        assert!(!hooks.is_pending(id));
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            "msvcrt.dll+0x1d50".parse(),
+            Ok(HookLocation::ModuleOffset {
+                module: "msvcrt.dll".to_string(),
+                offset: 0x1d50
+            })
+        );
+        assert_eq!(
+            "libc.so.6 + 4096".parse(),
+            Ok(HookLocation::ModuleOffset {
+                module: "libc.so.6".to_string(),
+                offset: 4096
+            })
+        );
+        assert_eq!("0x6ff6cd50".parse(), Ok(HookLocation::Address(0x6ff6cd50)));
+        assert_eq!(
+            "operator new".parse(),
+            Ok(HookLocation::Symbol("operator new".to_string()))
+        );
+        assert!("+0x10".parse::<HookLocation>().is_err());
+        assert!("0xzz".parse::<HookLocation>().is_err());
+        // Without a valid offset the plus is part of the symbol.
+        assert_eq!(
+            "operator+".parse(),
+            Ok(HookLocation::Symbol("operator+".to_string()))
+        );
+        assert_eq!(
+            "operator+=".parse(),
+            Ok(HookLocation::Symbol("operator+=".to_string()))
+        );
+        assert_eq!(
+            "foo.dll+0xzz".parse(),
+            Ok(HookLocation::Symbol("foo.dll+0xzz".to_string()))
+        );
+    }
+}
@@ -4,6 +4,56 @@ pub mod api;
 /// Some wrappers to make things a lot more convenient.
 pub mod wrappers;
 
+/// Actor thread owning the debugger, with a handle that can be shared between threads.
+pub mod actor;
+
+/// Conversion between values in the process and Rust types.
+pub mod value;
+
+/// Serializable snapshots of value trees, requires the `serde` feature.
+#[cfg(feature = "serde")]
+pub mod value_tree;
+
+/// Register sets and architecture neutral register aliases.
+pub mod registers;
+
+/// Expression evaluation options and errors.
+pub mod expression;
+
+/// Turning addresses into modules, symbols and source locations.
+pub mod symbolication;
+
+/// Call stacks captured as plain data.
+pub mod backtrace;
+
+/// Decoding of events into plain data.
+pub mod events;
+
+/// Async event stream and process control, requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub mod async_events;
+
+/// Readers and writers for the stdio of a process.
+pub mod process_io;
+
+/// Event loop dispatching process stops and events to handlers.
+pub mod event_loop;
+
+/// Bookkeeping for the limited hardware watchpoint slots.
+pub mod watchpoints;
+
+/// Calling conventions to obtain function arguments and return values.
+pub mod calling_convention;
+
+/// Hooks on function entry and return.
+pub mod hooks;
+
+/// Ready to use tools built on top of the hooks.
+pub mod tools;
+
+// Allows the derive macros to refer to this crate as `lldb` from within it.
+extern crate self as lldb;
+
 /// Re-export autocxx, consumers will likely want to use `autocxx::prelude::*`.
 pub use autocxx;
 
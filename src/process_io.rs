@@ -0,0 +1,179 @@
+use crate::api::ffi::lldb as bindings;
+use crate::events::ProcessEvents;
+use crate::wrappers::*;
+use std::io;
+use std::time::Duration;
+
+// LLDB buffers the output of processes it launched with their stdio going to the debugger, and
+// broadcasts an event whenever new output is available. The readers here wait on their own listener
+// for those events, so they can be used while something else drives the process. Output is consumed
+// by whoever reads it first, so use only one reader per stream.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Reads the stdout or stderr of a process, obtained from `Process::stdout_reader()` or
+/// `Process::stderr_reader()`. Reading blocks until output is available, returns end of file once
+/// the process exited and all of its output has been read.
+pub struct OutputReader {
+    process: Wrapped<bindings::SBProcess>,
+    listener: Wrapped<bindings::SBListener>,
+    stream: Stream,
+    timeout: Option<Duration>,
+}
+
+impl OutputReader {
+    fn new(process: Wrapped<bindings::SBProcess>, stream: Stream) -> OutputReader {
+        let (name, bits) = match stream {
+            Stream::Stdout => ("lldb_rs.stdout", ProcessEvents::STDOUT),
+            Stream::Stderr => ("lldb_rs.stderr", ProcessEvents::STDERR),
+        };
+        let mut listener = Wrapped::<bindings::SBListener>::with_name(name);
+        listener.start_listening_for_events(
+            &process.broadcaster(),
+            bits | ProcessEvents::STATE_CHANGED,
+        );
+        OutputReader {
+            process,
+            listener,
+            stream,
+            timeout: None,
+        }
+    }
+
+    /// Fail reads with `TimedOut` if no output arrives within this duration, by default reads block
+    /// until there is output. Useful when the process is stopped and won't produce any. The timeout
+    /// is rounded up to whole seconds.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Read whatever output is currently buffered, without blocking.
+    pub fn read_available(&mut self) -> Vec<u8> {
+        let mut output = vec![];
+        let mut buffer = [0u8; 4096];
+        loop {
+            let len = self.read_buffered(&mut buffer);
+            if len == 0 {
+                return output;
+            }
+            output.extend_from_slice(&buffer[..len]);
+        }
+    }
+
+    fn read_buffered(&mut self, buffer: &mut [u8]) -> usize {
+        match self.stream {
+            Stream::Stdout => self.process.get_stdout(buffer),
+            Stream::Stderr => self.process.get_stderr(buffer),
+        }
+    }
+
+    fn is_finished(&mut self) -> bool {
+        !self.process.as_ref().IsValid()
+            || matches!(
+                self.process.get_state(),
+                bindings::StateType::eStateExited | bindings::StateType::eStateDetached
+            )
+    }
+}
+
+impl io::Read for OutputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let len = self.read_buffered(buf);
+            if len > 0 {
+                return Ok(len);
+            }
+            if self.is_finished() {
+                // Output may have been buffered between the read and the state check.
+                return Ok(self.read_buffered(buf));
+            }
+            // Any event is a reason to look again, we only subscribed to relevant ones.
+            match self.timeout {
+                Some(timeout) => {
+                    if self.listener.wait_for_event(timeout).is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("no output within {timeout:?}"),
+                        ));
+                    }
+                }
+                None => {
+                    self.listener.wait_for_event(Duration::from_secs(1));
+                }
+            }
+        }
+    }
+}
+
+/// Writes to the stdin of a process, obtained from `Process::stdin_writer()`.
+pub struct InputWriter {
+    process: Wrapped<bindings::SBProcess>,
+}
+
+impl io::Write for InputWriter {
+    /// Fails with `BrokenPipe` if the process doesn't accept input, like attached processes.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        match self.process.put_stdin(buf) {
+            0 => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "process doesn't accept input",
+            )),
+            len => Ok(len),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) fn stdout_reader(process: Wrapped<bindings::SBProcess>) -> OutputReader {
+    OutputReader::new(process, Stream::Stdout)
+}
+
+pub(crate) fn stderr_reader(process: Wrapped<bindings::SBProcess>) -> OutputReader {
+    OutputReader::new(process, Stream::Stderr)
+}
+
+pub(crate) fn stdin_writer(process: Wrapped<bindings::SBProcess>) -> InputWriter {
+    InputWriter { process }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_default_process() {
+        bindings::SBDebugger::Initialize();
+        // An invalid process has no output and reads end of file right away.
+        let mut stdout = stdout_reader(bindings::SBProcess::new().wrap());
+        assert!(stdout.read_available().is_empty());
+        let mut buf = [0u8; 16];
+        assert_eq!(stdout.read(&mut buf).expect("eof"), 0);
+        let mut stderr = stderr_reader(bindings::SBProcess::new().wrap());
+        let mut output = vec![];
+        assert_eq!(stderr.read_to_end(&mut output).expect("eof"), 0);
+
+        let mut stdin = stdin_writer(bindings::SBProcess::new().wrap());
+        assert_eq!(stdin.write(&[]).expect("empty write"), 0);
+        let e = stdin.write(b"input").expect_err("no stdin");
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(
+            stdin.write_all(b"input").expect_err("no stdin").kind(),
+            io::ErrorKind::BrokenPipe
+        );
+        stdin.flush().expect("flush");
+    }
+}
@@ -0,0 +1,284 @@
+use crate::api::ffi::lldb as bindings;
+use crate::calling_convention::Arch;
+use crate::wrappers::*;
+
+// The register sets come from SBFrame::GetRegisters, each set is a value whose children are the
+// registers. Their names and the set names depend on the architecture, the aliases map the common
+// roles onto the register names for the architecture from the target triple. Multi byte reads
+// assume a little endian target, which all supported architectures are.
+
+/// The kind of a register set, derived from the name LLDB gives it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RegisterSetKind {
+    General,
+    FloatingPoint,
+    Vector,
+    Other,
+}
+
+impl RegisterSetKind {
+    pub fn from_name(name: &str) -> RegisterSetKind {
+        let name = name.to_lowercase();
+        if name.contains("general") {
+            RegisterSetKind::General
+        } else if name.contains("floating") {
+            RegisterSetKind::FloatingPoint
+        } else if name.contains("vector") {
+            RegisterSetKind::Vector
+        } else {
+            RegisterSetKind::Other
+        }
+    }
+}
+
+/// Register names for common roles on an architecture.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RegisterAliases {
+    pub pc: &'static str,
+    pub sp: &'static str,
+    pub fp: &'static str,
+    pub return_value: &'static str,
+}
+
+impl RegisterAliases {
+    pub fn for_arch(arch: Arch) -> Option<RegisterAliases> {
+        let (pc, sp, fp, return_value) = match arch {
+            Arch::X86 => ("eip", "esp", "ebp", "eax"),
+            Arch::X86_64 => ("rip", "rsp", "rbp", "rax"),
+            Arch::Arm => ("pc", "sp", "r11", "r0"),
+            Arch::Aarch64 => ("pc", "sp", "fp", "x0"),
+            Arch::Unknown => return None,
+        };
+        Some(RegisterAliases {
+            pc,
+            sp,
+            fp,
+            return_value,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegisterError {
+    /// LLDB returned an error, with its description.
+    Lldb(String),
+    /// No register with this name on the architecture.
+    NotFound { name: String, arch: Arch },
+    /// The register doesn't fit in the requested type, holds its size in bytes.
+    TooLarge { name: String, size: usize },
+    /// The aliases aren't known for this architecture.
+    UnknownArch,
+}
+
+impl std::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RegisterError::Lldb(e) => write!(f, "lldb error: {e}"),
+            RegisterError::NotFound { name, arch } => {
+                write!(f, "no register {name} on {arch:?}")
+            }
+            RegisterError::TooLarge { name, size } => {
+                write!(f, "register {name} is too large ({size} bytes)")
+            }
+            RegisterError::UnknownArch => write!(f, "unknown architecture"),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+impl From<Wrapped<bindings::SBError>> for RegisterError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        RegisterError::Lldb(e.get_str().to_string())
+    }
+}
+
+/// A set of registers, like the general purpose registers.
+pub struct RegisterSet {
+    name: String,
+    kind: RegisterSetKind,
+    value: Wrapped<bindings::SBValue>,
+}
+
+impl RegisterSet {
+    fn new(mut value: Wrapped<bindings::SBValue>) -> RegisterSet {
+        let name = value.get_name().unwrap_or_default();
+        RegisterSet {
+            kind: RegisterSetKind::from_name(&name),
+            name,
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> RegisterSetKind {
+        self.kind
+    }
+
+    /// Names of all registers in this set.
+    pub fn names(&mut self) -> Vec<String> {
+        self.registers()
+            .iter_mut()
+            .filter_map(|r| r.get_name().ok())
+            .collect()
+    }
+
+    pub fn registers(&mut self) -> Vec<Wrapped<bindings::SBValue>> {
+        self.value.children().unwrap_or_default()
+    }
+
+    pub fn get(&mut self, name: &str) -> Option<Wrapped<bindings::SBValue>> {
+        self.value.child_by_name(name).ok()
+    }
+}
+
+/// The registers of a frame, obtained through `Frame::registers()`.
+pub struct Registers {
+    arch: Arch,
+    sets: Vec<RegisterSet>,
+}
+
+impl Registers {
+    pub(crate) fn new(arch: Arch, sets: Vec<Wrapped<bindings::SBValue>>) -> Registers {
+        Registers {
+            arch,
+            sets: sets.into_iter().map(RegisterSet::new).collect(),
+        }
+    }
+
+    pub fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    pub fn sets(&mut self) -> &mut [RegisterSet] {
+        &mut self.sets
+    }
+
+    /// The first set of this kind.
+    pub fn set(&mut self, kind: RegisterSetKind) -> Option<&mut RegisterSet> {
+        self.sets.iter_mut().find(|s| s.kind == kind)
+    }
+
+    /// Find a register by name in any of the sets.
+    pub fn find(&mut self, name: &str) -> Result<Wrapped<bindings::SBValue>, RegisterError> {
+        self.sets
+            .iter_mut()
+            .find_map(|s| s.get(name))
+            .ok_or_else(|| RegisterError::NotFound {
+                name: name.to_string(),
+                arch: self.arch,
+            })
+    }
+
+    /// The raw bytes of the register, in target byte order.
+    pub fn read_bytes(&mut self, name: &str) -> Result<Vec<u8>, RegisterError> {
+        Ok(self.find(name)?.get_data()?.read_raw()?)
+    }
+
+    pub fn read_u64(&mut self, name: &str) -> Result<u64, RegisterError> {
+        let bytes = self.read_bytes(name)?;
+        Ok(from_le_bytes(name, &bytes, 8)? as u64)
+    }
+
+    /// For registers up to 16 bytes, like the xmm registers.
+    pub fn read_u128(&mut self, name: &str) -> Result<u128, RegisterError> {
+        let bytes = self.read_bytes(name)?;
+        from_le_bytes(name, &bytes, 16)
+    }
+
+    fn aliases(&self) -> Result<RegisterAliases, RegisterError> {
+        RegisterAliases::for_arch(self.arch).ok_or(RegisterError::UnknownArch)
+    }
+
+    /// The program counter.
+    pub fn pc(&mut self) -> Result<u64, RegisterError> {
+        let name = self.aliases()?.pc;
+        self.read_u64(name)
+    }
+
+    /// The stack pointer.
+    pub fn sp(&mut self) -> Result<u64, RegisterError> {
+        let name = self.aliases()?.sp;
+        self.read_u64(name)
+    }
+
+    /// The frame pointer, only meaningful if the code maintains one.
+    pub fn fp(&mut self) -> Result<u64, RegisterError> {
+        let name = self.aliases()?.fp;
+        self.read_u64(name)
+    }
+
+    /// The register holding integer and pointer return values.
+    pub fn return_value_register(&mut self) -> Result<u64, RegisterError> {
+        let name = self.aliases()?.return_value;
+        self.read_u64(name)
+    }
+}
+
+/// Little endian bytes into an integer, failing if it's larger than `max` bytes.
+fn from_le_bytes(name: &str, bytes: &[u8], max: usize) -> Result<u128, RegisterError> {
+    if bytes.len() > max {
+        return Err(RegisterError::TooLarge {
+            name: name.to_string(),
+            size: bytes.len(),
+        });
+    }
+    let mut full = [0u8; 16];
+    full[..bytes.len()].copy_from_slice(bytes);
+    Ok(u128::from_le_bytes(full))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_kind() {
+        assert_eq!(
+            RegisterSetKind::from_name("General Purpose Registers"),
+            RegisterSetKind::General
+        );
+        assert_eq!(
+            RegisterSetKind::from_name("Floating Point Registers"),
+            RegisterSetKind::FloatingPoint
+        );
+        assert_eq!(
+            RegisterSetKind::from_name("Advanced Vector Extensions"),
+            RegisterSetKind::Vector
+        );
+        assert_eq!(
+            RegisterSetKind::from_name("Exception State Registers"),
+            RegisterSetKind::Other
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        let x86 = RegisterAliases::for_arch(Arch::from_triple("i686-pc-windows-msvc")).unwrap();
+        assert_eq!(x86.pc, "eip");
+        assert_eq!(x86.return_value, "eax");
+        let a64 =
+            RegisterAliases::for_arch(Arch::from_triple("aarch64-unknown-linux-gnu")).unwrap();
+        assert_eq!(a64.fp, "fp");
+        assert!(RegisterAliases::for_arch(Arch::Unknown).is_none());
+    }
+
+    #[test]
+    fn test_from_le_bytes() {
+        assert_eq!(
+            from_le_bytes("eax", &[0x78, 0x56, 0x34, 0x12], 8),
+            Ok(0x12345678)
+        );
+        assert_eq!(from_le_bytes("xmm0", &[0xff; 16], 16), Ok(u128::MAX));
+        assert_eq!(
+            from_le_bytes("xmm0", &[0; 16], 8),
+            Err(RegisterError::TooLarge {
+                name: "xmm0".to_string(),
+                size: 16
+            })
+        );
+    }
+}
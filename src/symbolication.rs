@@ -0,0 +1,263 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+use std::collections::HashMap;
+
+// An address is resolved to a section relative address first, which tells the module. The symbol
+// context of that address holds the symbol from the symbol table, the function and line entry from
+// the debug info and the innermost block. Inlined functions are blocks within the function, walking
+// up through the inlined blocks gives the chain of inlined calls, each with the location it was
+// inlined at.
+
+/// Resolve all parts of the symbol context, `eSymbolContextEverything`.
+const RESOLVE_EVERYTHING: u32 = 0x7f;
+
+/// A function inlined at an address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InlinedFrame {
+    pub function: String,
+    /// Where the function was inlined into its caller.
+    pub call_file: Option<String>,
+    pub call_line: Option<u32>,
+    pub call_column: Option<u32>,
+}
+
+/// Everything known about a load address, fields are None if they couldn't be resolved, for
+/// example the source location of code without debug info.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolInfo {
+    pub address: u64,
+    /// File name of the module, like `kernel32.dll`.
+    pub module: Option<String>,
+    pub symbol: Option<String>,
+    /// Offset of the address from the start of the symbol.
+    pub offset: Option<u64>,
+    /// The function from the debug info, the outermost one if code got inlined into it.
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Functions inlined at this address, innermost first.
+    pub inlined_chain: Vec<InlinedFrame>,
+}
+
+impl SymbolInfo {
+    /// The name of the code at the address, the innermost inlined function if there is one.
+    pub fn name(&self) -> Option<&str> {
+        self.inlined_chain
+            .first()
+            .map(|f| f.function.as_str())
+            .or(self.function.as_deref())
+            .or(self.symbol.as_deref())
+    }
+}
+
+/// Formats like the frames of LLDB's `bt`, `kernel32.dll`HeapAlloc + 12 at heap.c:120:5`. The
+/// offset is relative to the outer function, so it follows that name when code got inlined.
+impl std::fmt::Display for SymbolInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "0x{:x}", self.address)?;
+        if let Some(module) = &self.module {
+            write!(f, " {module}`")?;
+        } else if self.name().is_some() {
+            write!(f, " ")?;
+        }
+        let outer = self.function.as_deref().or(self.symbol.as_deref());
+        let offset = self.offset.filter(|o| *o != 0);
+        match (self.inlined_chain.first(), outer) {
+            (Some(inlined), Some(outer)) => {
+                write!(f, "{outer}")?;
+                if let Some(offset) = offset {
+                    write!(f, " + {offset}")?;
+                }
+                write!(f, " [inlined] {}", inlined.function)?;
+            }
+            _ => {
+                if let Some(name) = self.name() {
+                    write!(f, "{name}")?;
+                    if let Some(offset) = offset {
+                        write!(f, " + {offset}")?;
+                    }
+                }
+            }
+        }
+        if let Some(file) = &self.file {
+            let file = file.rsplit(['/', '\\']).next().unwrap_or(file);
+            write!(f, " at {file}")?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+                if let Some(column) = self.column {
+                    write!(f, ":{column}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn non_zero(v: u32) -> Option<u32> {
+    (v != 0).then_some(v)
+}
+
+fn file_path<F: FileSpec>(file: &F) -> Option<String> {
+    file.is_valid().then(|| file.get_path())
+}
+
+/// Symbolicate a single load address, see [`Target::symbolicate`].
+pub fn symbolicate<T: Target>(target: &mut T, address: u64) -> SymbolInfo {
+    let mut info = SymbolInfo {
+        address,
+        ..Default::default()
+    };
+    let resolved = target.resolve_load_address(address);
+    if !resolved.is_valid() {
+        return info;
+    }
+    let mut context = target.resolve_symbol_context_for_address(&resolved, RESOLVE_EVERYTHING);
+
+    let module = context.get_module();
+    if module.is_valid() {
+        info.module = module.get_file_spec().get_filename();
+    }
+
+    let mut symbol = context.get_symbol();
+    if symbol.is_valid() {
+        info.symbol = symbol.get_name();
+        info.offset = symbol
+            .get_start_address()
+            .load_address(&*target)
+            .and_then(|start| address.checked_sub(start));
+    }
+
+    let mut function = context.get_function();
+    if function.is_valid() {
+        info.function = function.get_display_name().or_else(|| function.get_name());
+        if info.offset.is_none() {
+            info.offset = function
+                .get_start_address()
+                .load_address(&*target)
+                .and_then(|start| address.checked_sub(start));
+        }
+    }
+
+    let line_entry = context.get_line_entry();
+    if line_entry.is_valid() {
+        info.file = file_path(&line_entry.get_file_spec());
+        info.line = non_zero(line_entry.get_line());
+        info.column = non_zero(line_entry.get_column());
+    }
+
+    info.inlined_chain = inlined_chain(&mut context);
+    info
+}
+
+fn inlined_chain<C: SymbolContext>(context: &mut C) -> Vec<InlinedFrame> {
+    let mut res = vec![];
+    let mut block = context.get_block().get_containing_inlined_block();
+    while block.is_valid() && block.is_inlined() {
+        res.push(InlinedFrame {
+            function: block.get_inlined_name().unwrap_or_default(),
+            call_file: file_path(&block.get_inlined_call_site_file()),
+            call_line: non_zero(block.get_inlined_call_site_line()),
+            call_column: non_zero(block.get_inlined_call_site_column()),
+        });
+        block = block.get_parent().get_containing_inlined_block();
+    }
+    res
+}
+
+/// Symbolicates addresses and caches the results per address, for tracing where the same return
+/// addresses come by over and over.
+///
+/// Results depend on the loaded modules, call [`Symbolicator::clear`] when modules are loaded or
+/// unloaded.
+#[derive(Debug, Default)]
+pub struct Symbolicator {
+    cache: HashMap<u64, SymbolInfo>,
+}
+
+impl Symbolicator {
+    pub fn new() -> Symbolicator {
+        Default::default()
+    }
+
+    pub fn symbolicate<T: Target>(&mut self, target: &mut T, address: u64) -> &SymbolInfo {
+        self.cache
+            .entry(address)
+            .or_insert_with(|| symbolicate(target, address))
+    }
+
+    /// Symbolicate a batch of addresses, in the same order.
+    pub fn symbolicate_all<T: Target, I: IntoIterator<Item = u64>>(
+        &mut self,
+        target: &mut T,
+        addresses: I,
+    ) -> Vec<SymbolInfo> {
+        addresses
+            .into_iter()
+            .map(|a| self.symbolicate(target, a).clone())
+            .collect()
+    }
+
+    /// Number of cached addresses.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Forget all cached results.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use autocxx::prelude::*;
+
+    #[test]
+    fn test_display() {
+        let mut info = SymbolInfo {
+            address: 0x6ff6cd8a,
+            ..Default::default()
+        };
+        assert_eq!(info.to_string(), "0x6ff6cd8a");
+
+        info.module = Some("msvcrt.dll".to_string());
+        info.symbol = Some("malloc".to_string());
+        info.offset = Some(58);
+        assert_eq!(info.to_string(), "0x6ff6cd8a msvcrt.dll`malloc + 58");
+
+        info.function = Some("heap_alloc".to_string());
+        info.file = Some("/src/dlls/msvcrt/heap.c".to_string());
+        info.line = Some(120);
+        info.column = Some(5);
+        info.inlined_chain = vec![InlinedFrame {
+            function: "round_size".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(info.name(), Some("round_size"));
+        assert_eq!(
+            info.to_string(),
+            "0x6ff6cd8a msvcrt.dll`heap_alloc + 58 [inlined] round_size at heap.c:120:5"
+        );
+    }
+
+    #[test]
+    fn test_cache() {
+        bindings::SBDebugger::Initialize();
+        let mut dbg = bindings::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+        let mut symbolicator = Symbolicator::new();
+        let infos = symbolicator.symbolicate_all(&mut target, [0x1000, 0x2000, 0x1000]);
+        assert_eq!(infos.len(), 3);
+        assert_eq!(infos[0], infos[2]);
+        assert_eq!(infos[1].module, None);
+        assert_eq!(symbolicator.len(), 2);
+        symbolicator.clear();
+        assert!(symbolicator.is_empty());
+    }
+}
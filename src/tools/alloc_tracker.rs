@@ -0,0 +1,479 @@
+use crate::api::ffi::lldb as bindings;
+use crate::backtrace::Backtrace;
+use crate::calling_convention::{CallingConvention, Convention};
+use crate::hooks::*;
+use crate::symbolication::Symbolicator;
+use crate::wrappers::*;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// Tracks allocations by hooking the allocate, free and reallocate functions of an allocator. The
+// allocate and reallocate functions use return hooks to obtain the returned pointer, frees are
+// handled on entry. Once the process exits the report lists whatever is still alive as leaks.
+// Only the program counters of the call sites are recorded while tracking, they are symbolicated
+// when the report is created.
+
+/// What an allocator function does and which arguments matter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocKind {
+    /// Returns a new allocation of `size_arg` bytes.
+    Allocate { size_arg: usize },
+    /// Frees the pointer in `ptr_arg`.
+    Free { ptr_arg: usize },
+    /// Resizes the pointer in `ptr_arg` to `size_arg` bytes, returning the new pointer.
+    Reallocate { ptr_arg: usize, size_arg: usize },
+}
+
+/// A function of an allocator to hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocatorFunction {
+    pub location: HookLocation,
+    pub kind: AllocKind,
+    /// Convention to use, the target's default if None.
+    pub convention: Option<Convention>,
+}
+
+impl AllocatorFunction {
+    pub fn allocate(name: &str, size_arg: usize) -> AllocatorFunction {
+        AllocatorFunction::new(name, AllocKind::Allocate { size_arg })
+    }
+
+    pub fn free(name: &str, ptr_arg: usize) -> AllocatorFunction {
+        AllocatorFunction::new(name, AllocKind::Free { ptr_arg })
+    }
+
+    pub fn reallocate(name: &str, ptr_arg: usize, size_arg: usize) -> AllocatorFunction {
+        AllocatorFunction::new(name, AllocKind::Reallocate { ptr_arg, size_arg })
+    }
+
+    fn new(name: &str, kind: AllocKind) -> AllocatorFunction {
+        AllocatorFunction {
+            location: HookLocation::Symbol(name.to_string()),
+            kind,
+            convention: None,
+        }
+    }
+
+    /// Use a function address instead of the symbol name.
+    pub fn at_address(mut self, address: u64) -> AllocatorFunction {
+        self.location = HookLocation::Address(address);
+        self
+    }
+
+    pub fn with_convention(mut self, convention: Convention) -> AllocatorFunction {
+        self.convention = Some(convention);
+        self
+    }
+}
+
+/// malloc, free and realloc from the C library.
+pub fn libc_allocator() -> Vec<AllocatorFunction> {
+    vec![
+        AllocatorFunction::allocate("malloc", 0),
+        AllocatorFunction::free("free", 0),
+        AllocatorFunction::reallocate("realloc", 0, 1),
+    ]
+}
+
+/// HeapAlloc, HeapFree and HeapReAlloc from the Windows API.
+pub fn windows_heap_allocator() -> Vec<AllocatorFunction> {
+    vec![
+        AllocatorFunction::allocate("HeapAlloc", 2),
+        AllocatorFunction::free("HeapFree", 2),
+        AllocatorFunction::reallocate("HeapReAlloc", 2, 3),
+    ]
+}
+
+/// A live allocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    pub address: u64,
+    pub size: u64,
+    pub thread_id: u64,
+    /// The call site, starting at the caller of the allocator function. Bare program counters
+    /// until the report symbolicates them.
+    pub backtrace: Backtrace,
+}
+
+/// Problems detected while tracking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllocIssue {
+    /// A pointer was freed that was freed before.
+    DoubleFree { address: u64, backtrace: Backtrace },
+    /// A pointer was freed that was never returned by the allocator.
+    UnknownFree { address: u64, backtrace: Backtrace },
+}
+
+#[derive(Debug, Default)]
+struct State {
+    live: HashMap<u64, Allocation>,
+    freed: HashSet<u64>,
+    issues: Vec<AllocIssue>,
+    allocations: usize,
+    frees: usize,
+}
+
+impl State {
+    fn on_allocate(&mut self, address: u64, size: u64, thread_id: u64, backtrace: Backtrace) {
+        if address == 0 {
+            return;
+        }
+        self.allocations += 1;
+        self.freed.remove(&address);
+        self.live.insert(
+            address,
+            Allocation {
+                address,
+                size,
+                thread_id,
+                backtrace,
+            },
+        );
+    }
+
+    fn on_free(&mut self, address: u64, backtrace: Backtrace) {
+        if address == 0 {
+            return;
+        }
+        self.frees += 1;
+        if self.live.remove(&address).is_some() {
+            self.freed.insert(address);
+        } else if self.freed.contains(&address) {
+            self.issues
+                .push(AllocIssue::DoubleFree { address, backtrace });
+        } else {
+            self.issues
+                .push(AllocIssue::UnknownFree { address, backtrace });
+        }
+    }
+
+    fn on_reallocate(
+        &mut self,
+        old: u64,
+        new: u64,
+        size: u64,
+        thread_id: u64,
+        backtrace: Backtrace,
+    ) {
+        if new == 0 && size != 0 {
+            // Failed, the old allocation is untouched.
+            return;
+        }
+        if old != 0 {
+            self.on_free(old, backtrace.clone());
+        }
+        self.on_allocate(new, size, thread_id, backtrace);
+    }
+
+    fn report(&self) -> AllocReport {
+        let mut leaks: Vec<Allocation> = self.live.values().cloned().collect();
+        leaks.sort_by_key(|a| a.address);
+        AllocReport {
+            leaks,
+            issues: self.issues.clone(),
+            allocations: self.allocations,
+            frees: self.frees,
+        }
+    }
+}
+
+/// Summary of the tracked allocations, anything still live is considered leaked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocReport {
+    pub leaks: Vec<Allocation>,
+    pub issues: Vec<AllocIssue>,
+    pub allocations: usize,
+    pub frees: usize,
+}
+
+/// Leaks grouped by the call site that allocated them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakSite {
+    pub backtrace: Backtrace,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+impl AllocReport {
+    /// Resolve the symbols of all backtraces, every distinct pc is looked up once.
+    pub fn symbolicate<T: Target>(&mut self, target: &mut T) {
+        let mut symbolicator = Symbolicator::new();
+        for leak in self.leaks.iter_mut() {
+            leak.backtrace = leak.backtrace.symbolicate(target, &mut symbolicator);
+        }
+        for issue in self.issues.iter_mut() {
+            let backtrace = match issue {
+                AllocIssue::DoubleFree { backtrace, .. } => backtrace,
+                AllocIssue::UnknownFree { backtrace, .. } => backtrace,
+            };
+            *backtrace = backtrace.symbolicate(target, &mut symbolicator);
+        }
+    }
+
+    pub fn leaked_bytes(&self) -> u64 {
+        self.leaks.iter().map(|a| a.size).sum()
+    }
+
+    /// The leaks grouped by identical call stacks, most bytes first.
+    pub fn leak_sites(&self) -> Vec<LeakSite> {
+        let mut sites: HashMap<&Backtrace, LeakSite> = HashMap::new();
+        for leak in self.leaks.iter() {
+            let site = sites.entry(&leak.backtrace).or_insert_with(|| LeakSite {
+                backtrace: leak.backtrace.clone(),
+                count: 0,
+                bytes: 0,
+            });
+            site.count += 1;
+            site.bytes += leak.size;
+        }
+        let mut sites: Vec<LeakSite> = sites.into_values().collect();
+        sites.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then(a.backtrace.stable_hash().cmp(&b.backtrace.stable_hash()))
+        });
+        sites
+    }
+}
+
+fn write_backtrace(f: &mut std::fmt::Formatter, backtrace: &Backtrace) -> std::fmt::Result {
+    for line in backtrace.to_string().lines() {
+        writeln!(f, "    {line}")?;
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for AllocReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} allocations, {} frees, {} leaks totalling {} bytes",
+            self.allocations,
+            self.frees,
+            self.leaks.len(),
+            self.leaked_bytes()
+        )?;
+        for site in self.leak_sites() {
+            writeln!(
+                f,
+                "  {} leaks totalling {} bytes from",
+                site.count, site.bytes
+            )?;
+            write_backtrace(f, &site.backtrace)?;
+        }
+        for issue in self.issues.iter() {
+            let backtrace = match issue {
+                AllocIssue::DoubleFree { address, backtrace } => {
+                    writeln!(f, "  double free of 0x{address:x} from")?;
+                    backtrace
+                }
+                AllocIssue::UnknownFree { address, backtrace } => {
+                    writeln!(f, "  free of unknown 0x{address:x} from")?;
+                    backtrace
+                }
+            };
+            write_backtrace(f, backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// Capture the program counters of the calling frames, skipping the allocator function itself.
+fn call_site_backtrace(frame: &mut Wrapped<bindings::SBFrame>, max_depth: usize) -> Backtrace {
+    crate::backtrace::capture_pcs(&mut frame.thread(), 1, max_depth)
+}
+
+/// Tracks allocations of the configured allocator functions.
+pub struct AllocTracker {
+    functions: Vec<AllocatorFunction>,
+    max_backtrace: usize,
+    state: Rc<RefCell<State>>,
+}
+
+impl AllocTracker {
+    pub fn new(functions: Vec<AllocatorFunction>) -> AllocTracker {
+        AllocTracker {
+            functions,
+            max_backtrace: 16,
+            state: Default::default(),
+        }
+    }
+
+    /// Maximum number of frames to record for every call site.
+    pub fn max_backtrace(mut self, depth: usize) -> AllocTracker {
+        self.max_backtrace = depth;
+        self
+    }
+
+    /// Register the hooks for all allocator functions.
+    pub fn install<T: Target>(
+        &self,
+        target: &mut T,
+        hooks: &mut FunctionHooks,
+    ) -> HookResult<Vec<HookId>> {
+        let default = Convention::for_target(target);
+        let mut ids = vec![];
+        for function in self.functions.iter() {
+            let convention = function
+                .convention
+                .or(default)
+                .ok_or_else(|| format!("no calling convention for {}", target.get_triple()))?;
+            let state = self.state.clone();
+            let depth = self.max_backtrace;
+            let hook = FunctionHook::new(function.location.clone());
+            let id = match function.kind {
+                AllocKind::Allocate { size_arg } => {
+                    let hook = hook
+                        .on_entry(move |_, frame| {
+                            let size = convention.arg(frame, size_arg)?;
+                            Ok((size, call_site_backtrace(frame, depth)))
+                        })
+                        .on_return(move |call, (size, backtrace), frame| {
+                            let address = convention.return_value(frame)?;
+                            state.borrow_mut().on_allocate(
+                                address,
+                                size,
+                                call.thread_id,
+                                backtrace,
+                            );
+                            Ok(())
+                        });
+                    hooks.register(target, hook)?
+                }
+                AllocKind::Free { ptr_arg } => {
+                    let hook = hook.on_entry(move |_, frame| {
+                        let address = convention.arg(frame, ptr_arg)?;
+                        let backtrace = call_site_backtrace(frame, depth);
+                        state.borrow_mut().on_free(address, backtrace);
+                        Ok(())
+                    });
+                    hooks.register(target, hook)?
+                }
+                AllocKind::Reallocate { ptr_arg, size_arg } => {
+                    let hook = hook
+                        .on_entry(move |_, frame| {
+                            let old = convention.arg(frame, ptr_arg)?;
+                            let size = convention.arg(frame, size_arg)?;
+                            Ok((old, size, call_site_backtrace(frame, depth)))
+                        })
+                        .on_return(move |call, (old, size, backtrace), frame| {
+                            let new = convention.return_value(frame)?;
+                            state.borrow_mut().on_reallocate(
+                                old,
+                                new,
+                                size,
+                                call.thread_id,
+                                backtrace,
+                            );
+                            Ok(())
+                        });
+                    hooks.register(target, hook)?
+                }
+            };
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Allocations that are currently live.
+    pub fn live(&self) -> Vec<Allocation> {
+        self.state.borrow().live.values().cloned().collect()
+    }
+
+    /// Create the report with symbolicated backtraces, call this when the process has exited to
+    /// obtain the leaks. The modules must still be known to the target, so before the target is
+    /// relaunched or destroyed.
+    pub fn report<T: Target>(&self, target: &mut T) -> AllocReport {
+        let mut report = self.state.borrow().report();
+        report.symbolicate(target);
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use autocxx::prelude::*;
+
+    #[test]
+    fn test_state_tracking() {
+        let mut state = State::default();
+        state.on_allocate(0x1000, 16, 1, Backtrace::from_pcs(&[0x40]));
+        state.on_allocate(0x2000, 32, 1, Backtrace::from_pcs(&[0x41]));
+        state.on_free(0x1000, Backtrace::from_pcs(&[0x50]));
+        state.on_free(0x1000, Backtrace::from_pcs(&[0x51]));
+        state.on_free(0x3000, Backtrace::from_pcs(&[0x52]));
+        // Null frees are fine.
+        state.on_free(0, Backtrace::default());
+
+        let report = state.report();
+        assert_eq!(report.allocations, 2);
+        assert_eq!(report.frees, 3);
+        assert_eq!(report.leaks.len(), 1);
+        assert_eq!(report.leaks[0].address, 0x2000);
+        assert_eq!(report.leaked_bytes(), 32);
+        assert_eq!(
+            report.issues,
+            vec![
+                AllocIssue::DoubleFree {
+                    address: 0x1000,
+                    backtrace: Backtrace::from_pcs(&[0x51])
+                },
+                AllocIssue::UnknownFree {
+                    address: 0x3000,
+                    backtrace: Backtrace::from_pcs(&[0x52])
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_reallocate() {
+        let mut state = State::default();
+        // realloc(NULL, 8) behaves like malloc.
+        state.on_reallocate(0, 0x1000, 8, 1, Backtrace::default());
+        // A failed realloc keeps the old allocation.
+        state.on_reallocate(0x1000, 0, 64, 1, Backtrace::default());
+        assert_eq!(state.live.len(), 1);
+        state.on_reallocate(0x1000, 0x2000, 64, 1, Backtrace::default());
+        assert_eq!(state.live.len(), 1);
+        assert_eq!(state.live[&0x2000].size, 64);
+        // Reusing a freed address is not a double free.
+        state.on_allocate(0x1000, 4, 1, Backtrace::default());
+        state.on_free(0x1000, Backtrace::default());
+        assert!(state.report().issues.is_empty());
+    }
+
+    #[test]
+    fn test_leak_sites() {
+        let mut state = State::default();
+        state.on_allocate(0x1000, 16, 1, Backtrace::from_pcs(&[0x40, 0x80]));
+        state.on_allocate(0x2000, 16, 2, Backtrace::from_pcs(&[0x40, 0x80]));
+        state.on_allocate(0x3000, 24, 1, Backtrace::from_pcs(&[0x41, 0x80]));
+        let sites = state.report().leak_sites();
+        assert_eq!(sites.len(), 2);
+        assert_eq!(sites[0].backtrace.pcs(), vec![0x40, 0x80]);
+        assert_eq!((sites[0].count, sites[0].bytes), (2, 32));
+        assert_eq!((sites[1].count, sites[1].bytes), (1, 24));
+    }
+
+    #[test]
+    fn test_symbolicate_report() {
+        bindings::SBDebugger::Initialize();
+        let mut dbg = bindings::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+        let mut state = State::default();
+        state.on_allocate(0x1000, 16, 1, Backtrace::from_pcs(&[0x40, 0x80]));
+        state.on_free(0x3000, Backtrace::from_pcs(&[0x40]));
+        let mut report = state.report();
+        report.symbolicate(&mut target);
+        // Nothing is loaded, the pcs stay as they are.
+        assert_eq!(report.leaks[0].backtrace.pcs(), vec![0x40, 0x80]);
+        assert!(report.leaks[0].backtrace.frames[0].symbol.is_none());
+        assert!(matches!(
+            &report.issues[0],
+            AllocIssue::UnknownFree { backtrace, .. } if backtrace.pcs() == vec![0x40]
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+/// Tracking of heap allocations through hooks on the allocator functions.
+pub mod alloc_tracker;
@@ -0,0 +1,376 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+
+/// Derive `FromValue` for structs, mapping fields onto the named children of a value. Tuple
+/// structs map onto the children by index. A field can be mapped to a differently named child with
+/// `#[lldb(name = "child")]`.
+pub use lldb_derive::FromValue;
+
+/// Longest string read through a `char*`.
+const MAX_STRING_LENGTH: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueError {
+    /// LLDB returned an error, with its description.
+    Lldb(String),
+    /// The value can't be converted into the requested type.
+    Type { expected: String, found: String },
+    /// The value doesn't fit the requested type.
+    OutOfRange { expected: String, value: String },
+    /// A pointer that had to be followed was null.
+    Null,
+    /// A field or element failed to convert.
+    Child {
+        name: String,
+        error: Box<ValueError>,
+    },
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValueError::Lldb(e) => write!(f, "lldb error: {e}"),
+            ValueError::Type { expected, found } => {
+                write!(f, "expected {expected}, found value of type {found}")
+            }
+            ValueError::OutOfRange { expected, value } => {
+                write!(f, "value {value} does not fit in {expected}")
+            }
+            ValueError::Null => write!(f, "null pointer"),
+            ValueError::Child { name, error } => write!(f, "{name}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl From<Wrapped<bindings::SBError>> for ValueError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        ValueError::Lldb(e.get_str().to_string())
+    }
+}
+
+impl ValueError {
+    fn child(name: &str, error: ValueError) -> ValueError {
+        ValueError::Child {
+            name: name.to_string(),
+            error: Box::new(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteError {
+    /// LLDB refused the write, with its description.
+    Lldb(String),
+    /// The value can't be modified, like the result of an expression. Holds the name of the value.
+    ReadOnly(String),
+    /// The frame doesn't have a register with this name.
+    RegisterNotAvailable(String),
+    /// The new value has a different size than the value being written, in bytes.
+    SizeMismatch { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WriteError::Lldb(e) => write!(f, "lldb error: {e}"),
+            WriteError::ReadOnly(name) => write!(f, "{name} is read only"),
+            WriteError::RegisterNotAvailable(name) => write!(f, "register {name} not available"),
+            WriteError::SizeMismatch { expected, found } => {
+                write!(f, "size mismatch, value has {expected} bytes, got {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+impl From<Wrapped<bindings::SBError>> for WriteError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        WriteError::Lldb(e.get_str().to_string())
+    }
+}
+
+/// Conversion of a Rust value into the string LLDB parses when setting a value.
+pub trait ToValue {
+    fn to_value_string(&self) -> String;
+}
+
+macro_rules! impl_to_value {
+    ($($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value_string(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+impl_to_value!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl ToValue for bool {
+    fn to_value_string(&self) -> String {
+        (*self as u8).to_string()
+    }
+}
+
+macro_rules! impl_to_value_float {
+    ($($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value_string(&self) -> String {
+                    // Debug formatting round trips and always has a decimal point.
+                    format!("{self:?}")
+                }
+            }
+        )*
+    };
+}
+impl_to_value_float!(f32, f64);
+
+/// Conversion of a value in the process into a Rust type.
+pub trait FromValue: Sized {
+    /// Whether the conversion takes the pointer itself, like String for a `char*`, instead of the
+    /// value it points to. Determines what `Option<T>` passes on for non-null pointers.
+    const FROM_POINTER: bool = false;
+
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError>;
+}
+
+fn type_error<V: Value>(value: &mut V, expected: &str) -> ValueError {
+    ValueError::Type {
+        expected: expected.to_string(),
+        found: value.type_name().unwrap_or_default(),
+    }
+}
+
+macro_rules! impl_from_value_int {
+    ($getter:ident, $($t:ty),*) => {
+        $(
+            impl FromValue for $t {
+                fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+                    value.check()?;
+                    let raw = value.$getter()?;
+                    <$t>::try_from(raw).map_err(|_| ValueError::OutOfRange {
+                        expected: stringify!($t).to_string(),
+                        value: raw.to_string(),
+                    })
+                }
+            }
+        )*
+    };
+}
+impl_from_value_int!(get_value_unsigned, u8, u16, u32, u64, usize);
+impl_from_value_int!(get_value_signed, i8, i16, i32, i64, isize);
+
+impl FromValue for bool {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        value.check()?;
+        Ok(value.get_value_unsigned()? != 0)
+    }
+}
+
+impl FromValue for char {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        value.check()?;
+        let raw = value.get_value_unsigned()?;
+        u32::try_from(raw)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(ValueError::OutOfRange {
+                expected: "char".to_string(),
+                value: raw.to_string(),
+            })
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        let mut data = value.get_data()?;
+        match data.get_byte_size() {
+            4 => Ok(data.get_float(0)? as f64),
+            8 => Ok(data.get_double(0)?),
+            _ => Err(type_error(value, "float or double")),
+        }
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        Ok(f64::from_value(value)? as f32)
+    }
+}
+
+/// Whether this is one of the C character types.
+fn is_char_type<T: Type>(t: &mut T) -> bool {
+    matches!(
+        t.get_canonical_type().get_basic_type(),
+        bindings::BasicType::eBasicTypeChar
+            | bindings::BasicType::eBasicTypeSignedChar
+            | bindings::BasicType::eBasicTypeUnsignedChar
+    )
+}
+
+/// Strings are read from `char*`, stopping at the terminator, or from `char[N]`, stopping at the
+/// first terminator or the end of the array.
+impl FromValue for String {
+    const FROM_POINTER: bool = true;
+
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        let mut t = value.get_type()?.get_canonical_type();
+        if t.is_pointer_type() && is_char_type(&mut t.get_pointee_type()) {
+            let address = value.get_value_unsigned()?;
+            if address == 0 {
+                return Err(ValueError::Null);
+            }
+            let s = value
+                .process()
+                .read_cstring_from_memory(address, MAX_STRING_LENGTH)?;
+            return Ok(s.to_string_lossy().into_owned());
+        }
+        if t.is_array_type() && is_char_type(&mut t.get_array_element_type()) {
+            let mut bytes = value.get_data()?.read_raw()?;
+            if let Some(end) = bytes.iter().position(|b| *b == 0) {
+                bytes.truncate(end);
+            }
+            return Ok(String::from_utf8_lossy(&bytes).into_owned());
+        }
+        Err(type_error(value, "char pointer or array"))
+    }
+}
+
+/// Arrays and anything else with children, like `std::vector` through its synthetic children.
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        value
+            .children()?
+            .iter_mut()
+            .enumerate()
+            .map(|(i, child)| {
+                T::from_value(child).map_err(|e| ValueError::child(&format!("[{i}]"), e))
+            })
+            .collect()
+    }
+}
+
+/// None for null pointers, otherwise the converted value, following the pointer unless `T` takes
+/// the pointer itself. Values that aren't pointers are always converted.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+        if !value.is_pointer()? {
+            return Ok(Some(T::from_value(value)?));
+        }
+        if value.get_value_unsigned()? == 0 {
+            return Ok(None);
+        }
+        if T::FROM_POINTER {
+            return Ok(Some(T::from_value(value)?));
+        }
+        Ok(Some(T::from_value(&mut value.dereference()?)?))
+    }
+}
+
+macro_rules! impl_from_value_tuple {
+    ($($t:ident $i:tt),*) => {
+        /// Tuples take the children by index.
+        impl<$($t: FromValue),*> FromValue for ($($t,)*) {
+            fn from_value<V: Value>(value: &mut V) -> Result<Self, ValueError> {
+                Ok(($(element::<$t, V>(value, $i)?,)*))
+            }
+        }
+    };
+}
+impl_from_value_tuple!(A 0);
+impl_from_value_tuple!(A 0, B 1);
+impl_from_value_tuple!(A 0, B 1, C 2);
+impl_from_value_tuple!(A 0, B 1, C 2, D 3);
+impl_from_value_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_from_value_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Follow the pointer if this is one, structs are often only reachable through pointers.
+fn pointee<V: Value>(value: &mut V) -> Result<Option<Wrapped<bindings::SBValue>>, ValueError> {
+    if !value.is_pointer()? {
+        return Ok(None);
+    }
+    if value.get_value_unsigned()? == 0 {
+        return Err(ValueError::Null);
+    }
+    Ok(Some(value.dereference()?))
+}
+
+/// Convert the child with this name, used by the derive.
+#[doc(hidden)]
+pub fn field<T: FromValue, V: Value>(value: &mut V, name: &str) -> Result<T, ValueError> {
+    let child = match pointee(value)? {
+        Some(mut target) => target.child_by_name(name),
+        None => value.child_by_name(name),
+    };
+    child
+        .map_err(ValueError::from)
+        .and_then(|mut c| T::from_value(&mut c))
+        .map_err(|e| ValueError::child(name, e))
+}
+
+/// Convert the child at this index, used by the derive.
+#[doc(hidden)]
+pub fn element<T: FromValue, V: Value>(value: &mut V, index: u32) -> Result<T, ValueError> {
+    let child = match pointee(value)? {
+        Some(mut target) => target.child_at_index(index),
+        None => value.child_at_index(index),
+    };
+    child
+        .map_err(ValueError::from)
+        .and_then(|mut c| T::from_value(&mut c))
+        .map_err(|e| ValueError::child(&index.to_string(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(FromValue)]
+    struct Config {
+        port: u16,
+        name: String,
+        #[lldb(name = "next_hop")]
+        next: Option<(u32, bool)>,
+        weights: Vec<f32>,
+    }
+
+    #[derive(FromValue)]
+    struct Point(i32, i32);
+
+    #[test]
+    fn test_derive() {
+        fn assert_from_value<T: FromValue>() {}
+        assert_from_value::<Config>();
+        assert_from_value::<Point>();
+        assert!(String::FROM_POINTER);
+        assert!(!Config::FROM_POINTER);
+    }
+
+    #[test]
+    fn test_to_value() {
+        assert_eq!((-3i8).to_value_string(), "-3");
+        assert_eq!(true.to_value_string(), "1");
+        assert_eq!(2.0f64.to_value_string(), "2.0");
+        assert_eq!(0.1f32.to_value_string(), "0.1");
+    }
+
+    #[test]
+    fn test_error_display() {
+        let e = ValueError::child(
+            "cfg",
+            ValueError::child(
+                "port",
+                ValueError::OutOfRange {
+                    expected: "u8".to_string(),
+                    value: "300".to_string(),
+                },
+            ),
+        );
+        assert_eq!(e.to_string(), "cfg: port: value 300 does not fit in u8");
+    }
+}
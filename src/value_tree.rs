@@ -0,0 +1,175 @@
+use crate::wrappers::*;
+use serde::Serialize;
+
+// Walks a value and its children into plain data that can be serialized. Pointers are followed by
+// dereferencing them, the addresses of the aggregates on the current path are tracked so a pointer
+// back to one of them is reported as a cycle instead of being followed.
+
+/// How to walk the value tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TreeOptions {
+    /// Levels of children to descend into, zero only produces the value itself.
+    pub depth: usize,
+    /// Children beyond this count are left out and the node is marked as truncated.
+    pub max_children: usize,
+    /// Dereference pointers, the pointee becomes the single child of the pointer.
+    pub follow_pointers: bool,
+    /// Use synthetic children, like the elements of a `std::vector` instead of its raw members.
+    pub synthetic_children: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            depth: 4,
+            max_children: 64,
+            follow_pointers: true,
+            synthetic_children: true,
+        }
+    }
+}
+
+/// A snapshot of a value and its children.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValueNode {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ValueNode>,
+    /// Children were left out because of the depth or child limits.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// A pointer to an aggregate that is already on the path, it isn't followed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+    /// Reading this value failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Snapshot the value with these options.
+pub fn snapshot<V: Value>(value: &mut V, options: &TreeOptions) -> SBResult<ValueNode> {
+    value.check()?;
+    let mut path = vec![];
+    Ok(walk(value, options, options.depth, &mut path))
+}
+
+fn walk<V: Value>(
+    value: &mut V,
+    options: &TreeOptions,
+    depth: usize,
+    path: &mut Vec<u64>,
+) -> ValueNode {
+    let mut node = ValueNode {
+        name: value.get_name().unwrap_or_default(),
+        type_name: value.type_name().unwrap_or_default(),
+        value: to_string_opt(value.pin_mut().GetValue()),
+        summary: value.summary().ok().flatten(),
+        address: value.load_address().ok(),
+        ..Default::default()
+    };
+    if let Err(e) = value.check() {
+        node.error = Some(e.get_str().to_string());
+        return node;
+    }
+
+    if value.is_pointer().unwrap_or(false) {
+        if !options.follow_pointers {
+            return node;
+        }
+        let target = match value.get_value_unsigned() {
+            Ok(0) | Err(_) => return node,
+            Ok(v) => v,
+        };
+        if path.contains(&target) {
+            node.cycle = true;
+            return node;
+        }
+        if depth == 0 {
+            node.truncated = true;
+            return node;
+        }
+        match value.dereference() {
+            Ok(mut pointee) => node
+                .children
+                .push(walk(&mut pointee, options, depth - 1, path)),
+            Err(e) => node.error = Some(e.get_str().to_string()),
+        }
+        return node;
+    }
+
+    let mut source = if options.synthetic_children {
+        None
+    } else {
+        value.non_synthetic_value().ok()
+    };
+    let count = match &mut source {
+        Some(v) => v.get_num_children(),
+        None => value.get_num_children(),
+    }
+    .unwrap_or(0);
+    if count == 0 {
+        return node;
+    }
+    if depth == 0 {
+        node.truncated = true;
+        return node;
+    }
+
+    let shown = count.min(options.max_children as u32);
+    node.truncated = shown < count;
+    if let Some(address) = node.address {
+        path.push(address);
+    }
+    for i in 0..shown {
+        let child = match &mut source {
+            Some(v) => v.child_at_index(i),
+            None => value.child_at_index(i),
+        };
+        node.children.push(match child {
+            Ok(mut child) => walk(&mut child, options, depth - 1, path),
+            Err(e) => ValueNode {
+                name: format!("[{i}]"),
+                error: Some(e.get_str().to_string()),
+                ..Default::default()
+            },
+        });
+    }
+    if node.address.is_some() {
+        path.pop();
+    }
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let node = ValueNode {
+            name: "cfg".to_string(),
+            type_name: "config *".to_string(),
+            value: Some("0x1000".to_string()),
+            children: vec![ValueNode {
+                name: "next".to_string(),
+                type_name: "config *".to_string(),
+                value: Some("0x1000".to_string()),
+                cycle: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&node).unwrap(),
+            r#"{"name":"cfg","type":"config *","value":"0x1000","children":[{"name":"next","type":"config *","value":"0x1000","cycle":true}]}"#
+        );
+    }
+}
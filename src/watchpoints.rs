@@ -0,0 +1,572 @@
+use crate::api::ffi::lldb as bindings;
+use crate::wrappers::*;
+use std::collections::HashMap;
+
+// Hardware watchpoints are implemented with debug registers, on x86 these can only watch 1, 2, 4
+// or (on 64 bits) 8 bytes and the address must be aligned to the watched length. Sending anything
+// else to the stub results in rather opaque errors like 'sending gdb watchpoint failed', so we
+// validate and split ranges here before anything is sent.
+// When we run out of hardware slots we can fall back to software watchpoints, these single step
+// the process and compare the watched memory after every step. This is very slow, but it works for
+// ranges of any size.
+
+/// A chunk of memory that can be watched by a single hardware watchpoint.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WatchChunk {
+    pub address: u64,
+    pub size: usize,
+}
+
+/// Handle to a watched range, which may be backed by multiple hardware watchpoints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WatchHandle(u64);
+
+/// How to advance the process between comparisons of software watched memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepMode {
+    /// Step a single instruction, reports the exact instruction that modified the memory.
+    Instruction,
+    /// Run to the next branch instruction, faster but the reported pc is the end of the block.
+    BasicBlock,
+}
+
+/// Synthetic watchpoint stop reason, produced when a software watched range changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftwareWatchHit {
+    pub handle: WatchHandle,
+    /// Start of the watched range.
+    pub address: u64,
+    /// Program counter after the step that modified the memory.
+    pub pc: u64,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+struct SoftwareWatch {
+    address: u64,
+    snapshot: Vec<u8>,
+}
+
+enum Watch {
+    Hardware(Vec<WatchpointId>),
+    Software(SoftwareWatch),
+}
+
+#[derive(Debug)]
+pub enum WatchpointError {
+    /// A watch of zero bytes was requested.
+    ZeroSize,
+    /// The range wraps around the end of the address space.
+    AddressOverflow { address: u64, size: usize },
+    /// The target reports an address size we can't split ranges for.
+    UnsupportedAddressSize(usize),
+    /// Not enough free hardware slots to watch this range.
+    InsufficientSlots {
+        address: u64,
+        size: usize,
+        required: usize,
+        available: usize,
+    },
+    /// The handle is not known to this manager.
+    UnknownHandle(WatchHandle),
+    /// Software watchpoints compare memory, so they can't detect reads.
+    SoftwareReadWatch { address: u64, size: usize },
+    /// Could only read part of the range for a software watchpoint.
+    ShortRead {
+        address: u64,
+        size: usize,
+        read: usize,
+    },
+    /// LLDB itself failed to set the watchpoint.
+    Lldb(Wrapped<bindings::SBError>),
+}
+
+impl std::fmt::Display for WatchpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WatchpointError::ZeroSize => write!(f, "cannot watch a range of zero bytes"),
+            WatchpointError::AddressOverflow { address, size } => write!(
+                f,
+                "range 0x{address:x} with size {size} wraps around the address space"
+            ),
+            WatchpointError::UnsupportedAddressSize(s) => {
+                write!(f, "unsupported address size of {s} bytes")
+            }
+            WatchpointError::InsufficientSlots {
+                address,
+                size,
+                required,
+                available,
+            } => write!(
+                f,
+                "watching 0x{address:x} with size {size} requires {required} hardware \
+                 watchpoints, only {available} available"
+            ),
+            WatchpointError::UnknownHandle(h) => write!(f, "unknown watch handle {h:?}"),
+            WatchpointError::SoftwareReadWatch { address, size } => write!(
+                f,
+                "cannot watch reads of 0x{address:x} with size {size} using a software watchpoint"
+            ),
+            WatchpointError::ShortRead {
+                address,
+                size,
+                read,
+            } => write!(
+                f,
+                "could only read {read} of {size} bytes at 0x{address:x} for software watchpoint"
+            ),
+            WatchpointError::Lldb(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchpointError {}
+
+impl From<Wrapped<bindings::SBError>> for WatchpointError {
+    fn from(e: Wrapped<bindings::SBError>) -> Self {
+        WatchpointError::Lldb(e)
+    }
+}
+
+/// Split a range into aligned chunks no larger than `max_chunk` bytes, `max_chunk` must be a
+/// power of two, usually the address size of the target.
+pub fn split_range(
+    address: u64,
+    size: usize,
+    max_chunk: usize,
+) -> Result<Vec<WatchChunk>, WatchpointError> {
+    if !max_chunk.is_power_of_two() {
+        return Err(WatchpointError::UnsupportedAddressSize(max_chunk));
+    }
+    if size == 0 {
+        return Err(WatchpointError::ZeroSize);
+    }
+    let end = address
+        .checked_add(size as u64)
+        .ok_or(WatchpointError::AddressOverflow { address, size })?;
+
+    let mut chunks = vec![];
+    let mut current = address;
+    while current < end {
+        let remaining = end - current;
+        // Largest power of two that is aligned, fits in the remainder and the hardware allows.
+        let mut chunk = max_chunk as u64;
+        while chunk > 1 && (current % chunk != 0 || chunk > remaining) {
+            chunk /= 2;
+        }
+        chunks.push(WatchChunk {
+            address: current,
+            size: chunk as usize,
+        });
+        current += chunk;
+    }
+    Ok(chunks)
+}
+
+/// Run to the next branch instruction and step over it.
+fn step_basic_block<T: Thread>(thread: &mut T) -> Result<(), Wrapped<bindings::SBError>> {
+    let pc = thread.frame(0).get_pc();
+    let mut target = thread.process().target();
+    let mut instructions = target.read_instructions(pc, 64);
+    for i in 0..instructions.get_size() {
+        let mut instruction = instructions.instruction(i as u32);
+        if instruction.does_branch() {
+            let address = instruction.get_load_address(&target);
+            if address != pc {
+                thread.run_to_address(address)?;
+            }
+            break;
+        }
+    }
+    // Either we're on the branch now, or there was no branch in the window, just step once.
+    thread.step_instruction(false)
+}
+
+/// Keeps track of the hardware watchpoint slots in use for a process and splits ranges into legal
+/// chunks before handing them to LLDB. If slots run out it can fall back to software watchpoints.
+pub struct WatchpointManager {
+    supported: usize,
+    address_size: usize,
+    watches: HashMap<WatchHandle, Watch>,
+    next_handle: u64,
+}
+
+impl WatchpointManager {
+    /// Create a manager using the limits reported by the target and process.
+    pub fn new<T: Target, P: Process>(
+        target: &mut T,
+        process: &mut P,
+    ) -> Result<WatchpointManager, WatchpointError> {
+        let supported = process.get_num_supported_hardware_watchpoints()?;
+        let address_size = target.get_address_byte_size();
+        Ok(WatchpointManager::with_limits(
+            supported as usize,
+            address_size as usize,
+        ))
+    }
+
+    /// Create a manager with explicit limits.
+    pub fn with_limits(supported: usize, address_size: usize) -> WatchpointManager {
+        WatchpointManager {
+            supported,
+            address_size,
+            watches: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Total number of hardware watchpoints supported.
+    pub fn supported(&self) -> usize {
+        self.supported
+    }
+
+    /// Number of hardware watchpoints that are not in use.
+    pub fn free_slots(&self) -> usize {
+        let used: usize = self
+            .watches
+            .values()
+            .map(|w| match w {
+                Watch::Hardware(ids) => ids.len(),
+                Watch::Software(_) => 0,
+            })
+            .sum();
+        self.supported.saturating_sub(used)
+    }
+
+    /// Whether any software watchpoints are active, if so the process must be advanced with
+    /// [`WatchpointManager::step_software`] instead of continued.
+    pub fn has_software_watches(&self) -> bool {
+        self.watches
+            .values()
+            .any(|w| matches!(w, Watch::Software(_)))
+    }
+
+    /// Whether this watch is implemented in software.
+    pub fn is_software(&self, handle: WatchHandle) -> bool {
+        matches!(self.watches.get(&handle), Some(Watch::Software(_)))
+    }
+
+    fn insert(&mut self, watch: Watch) -> WatchHandle {
+        let handle = WatchHandle(self.next_handle);
+        self.next_handle += 1;
+        self.watches.insert(handle, watch);
+        handle
+    }
+
+    /// Determine the chunks needed to watch this range, errors if there are not enough free
+    /// slots.
+    pub fn plan(&self, address: u64, size: usize) -> Result<Vec<WatchChunk>, WatchpointError> {
+        let chunks = split_range(address, size, self.address_size)?;
+        let available = self.free_slots();
+        if chunks.len() > available {
+            return Err(WatchpointError::InsufficientSlots {
+                address,
+                size,
+                required: chunks.len(),
+                available,
+            });
+        }
+        Ok(chunks)
+    }
+
+    /// Watch a range of memory, using as many hardware watchpoints as necessary.
+    pub fn watch<T: Target>(
+        &mut self,
+        target: &mut T,
+        address: u64,
+        size: usize,
+        read: bool,
+        write: bool,
+    ) -> Result<WatchHandle, WatchpointError> {
+        let chunks = self.plan(address, size)?;
+        let mut ids = vec![];
+        for chunk in chunks.iter() {
+            match target.watch_address(chunk.address, chunk.size, read, write) {
+                Ok(mut wp) => ids.push(wp.get_id()),
+                Err(e) => {
+                    // Don't leave half a watch behind.
+                    for id in ids {
+                        target.delete_watchpoint(id);
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(self.insert(Watch::Hardware(ids)))
+    }
+
+    /// Watch a range of memory for writes by comparing it after every step.
+    pub fn watch_software<P: Process>(
+        &mut self,
+        process: &mut P,
+        address: u64,
+        size: usize,
+    ) -> Result<WatchHandle, WatchpointError> {
+        if size == 0 {
+            return Err(WatchpointError::ZeroSize);
+        }
+        let snapshot = read_exact(process, address, size)?;
+        Ok(self.insert(Watch::Software(SoftwareWatch { address, snapshot })))
+    }
+
+    /// Watch a range of memory using hardware watchpoints, falling back to a software watchpoint
+    /// if there are not enough free slots. Software watchpoints can only detect writes, so watches
+    /// that include reads fail with [`WatchpointError::SoftwareReadWatch`] instead of silently
+    /// losing the read detection.
+    pub fn watch_with_fallback<T: Target, P: Process>(
+        &mut self,
+        target: &mut T,
+        process: &mut P,
+        address: u64,
+        size: usize,
+        read: bool,
+        write: bool,
+    ) -> Result<WatchHandle, WatchpointError> {
+        match self.watch(target, address, size, read, write) {
+            Err(WatchpointError::InsufficientSlots { .. }) => {
+                if read {
+                    return Err(WatchpointError::SoftwareReadWatch { address, size });
+                }
+                self.watch_software(process, address, size)
+            }
+            v => v,
+        }
+    }
+
+    /// Remove a watch, deleting all the watchpoints that back it.
+    pub fn unwatch<T: Target>(
+        &mut self,
+        target: &mut T,
+        handle: WatchHandle,
+    ) -> Result<(), WatchpointError> {
+        let watch = self
+            .watches
+            .remove(&handle)
+            .ok_or(WatchpointError::UnknownHandle(handle))?;
+        if let Watch::Hardware(ids) = watch {
+            for id in ids {
+                target.delete_watchpoint(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// The hardware watchpoints that back a watch, empty for software watches.
+    pub fn watchpoint_ids(&self, handle: WatchHandle) -> Option<&[WatchpointId]> {
+        self.watches.get(&handle).map(|w| match w {
+            Watch::Hardware(ids) => ids.as_slice(),
+            Watch::Software(_) => &[],
+        })
+    }
+
+    /// Find the watch a watchpoint belongs to, for example after a watchpoint stop.
+    pub fn find(&self, id: WatchpointId) -> Option<WatchHandle> {
+        self.watches
+            .iter()
+            .find(|(_, w)| matches!(w, Watch::Hardware(ids) if ids.contains(&id)))
+            .map(|(h, _)| *h)
+    }
+
+    /// Compare all software watched ranges against their snapshots, updating the snapshots and
+    /// returning a hit for every range that changed. All ranges are read before any snapshot is
+    /// updated, so a failed read leaves every snapshot as it was.
+    pub fn check_software<P: Process>(
+        &mut self,
+        process: &mut P,
+        pc: u64,
+    ) -> Result<Vec<SoftwareWatchHit>, WatchpointError> {
+        let mut reads = vec![];
+        for (handle, watch) in self.watches.iter() {
+            if let Watch::Software(watch) = watch {
+                let current = read_exact(process, watch.address, watch.snapshot.len())?;
+                reads.push((*handle, current));
+            }
+        }
+        let mut hits = vec![];
+        for (handle, current) in reads {
+            if let Some(Watch::Software(watch)) = self.watches.get_mut(&handle) {
+                if current != watch.snapshot {
+                    let old = std::mem::replace(&mut watch.snapshot, current.clone());
+                    hits.push(SoftwareWatchHit {
+                        handle,
+                        address: watch.address,
+                        pc,
+                        old,
+                        new: current,
+                    });
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Check the software watched ranges if the process is still stopped. A process that exited
+    /// or crashed has no memory left to compare, so it has no hits either.
+    fn check_stopped<P: Process>(
+        &mut self,
+        process: &mut P,
+        pc: u64,
+    ) -> Result<Vec<SoftwareWatchHit>, WatchpointError> {
+        if process.get_state() != bindings::StateType::eStateStopped {
+            return Ok(vec![]);
+        }
+        self.check_software(process, pc)
+    }
+
+    /// Advance the thread by one step and check the software watched ranges, returning no hits
+    /// if the process isn't stopped after the step.
+    /// Stepping is done without waiting for events, so the debugger should be in synchronous
+    /// mode while software watchpoints are in use.
+    pub fn step_software<T: Thread>(
+        &mut self,
+        thread: &mut T,
+        mode: StepMode,
+    ) -> Result<Vec<SoftwareWatchHit>, WatchpointError> {
+        match mode {
+            StepMode::Instruction => thread.step_instruction(false)?,
+            StepMode::BasicBlock => step_basic_block(thread)?,
+        }
+        let pc = thread.frame(0).get_pc();
+        let mut process = thread.process();
+        self.check_stopped(&mut process, pc)
+    }
+
+    /// Keep stepping until a software watched range changes, the process stops being in the
+    /// stopped state, or `max_steps` steps have been taken.
+    pub fn run_until_software_hit<T: Thread>(
+        &mut self,
+        thread: &mut T,
+        mode: StepMode,
+        max_steps: usize,
+    ) -> Result<Vec<SoftwareWatchHit>, WatchpointError> {
+        for _ in 0..max_steps {
+            let hits = self.step_software(thread, mode)?;
+            if !hits.is_empty() {
+                return Ok(hits);
+            }
+            if thread.process().get_state() != bindings::StateType::eStateStopped {
+                break;
+            }
+        }
+        Ok(vec![])
+    }
+}
+
+/// Read a range of memory, failing if not all of it could be read.
+fn read_exact<P: Process>(
+    process: &mut P,
+    address: u64,
+    size: usize,
+) -> Result<Vec<u8>, WatchpointError> {
+    let data = process.read_memory(address, size)?;
+    if data.len() != size {
+        return Err(WatchpointError::ShortRead {
+            address,
+            size,
+            read: data.len(),
+        });
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_aligned() {
+        let chunks = split_range(0x1000, 4, 4).unwrap();
+        assert_eq!(
+            chunks,
+            vec![WatchChunk {
+                address: 0x1000,
+                size: 4
+            }]
+        );
+        // Large ranges are split into address size chunks.
+        let chunks = split_range(0x1000, 96, 8).unwrap();
+        assert_eq!(chunks.len(), 12);
+        assert!(chunks.iter().all(|c| c.size == 8));
+    }
+
+    #[test]
+    fn test_split_misaligned() {
+        // 8 bytes on a 32 bits process become two 4 byte chunks.
+        let chunks = split_range(0x1000, 8, 4).unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        let chunks = split_range(0x1003, 6, 4).unwrap();
+        let sizes: Vec<usize> = chunks.iter().map(|c| c.size).collect();
+        assert_eq!(sizes, vec![1, 4, 1]);
+        for c in chunks.iter() {
+            assert_eq!(c.address % c.size as u64, 0);
+        }
+        assert_eq!(chunks.last().unwrap().address, 0x1008);
+    }
+
+    #[test]
+    fn test_split_errors() {
+        assert!(matches!(
+            split_range(0x1000, 0, 4),
+            Err(WatchpointError::ZeroSize)
+        ));
+        assert!(matches!(
+            split_range(u64::MAX, 2, 4),
+            Err(WatchpointError::AddressOverflow { .. })
+        ));
+        assert!(matches!(
+            split_range(0x1000, 4, 3),
+            Err(WatchpointError::UnsupportedAddressSize(3))
+        ));
+    }
+
+    #[test]
+    fn test_plan_slots() {
+        let manager = WatchpointManager::with_limits(4, 4);
+        assert_eq!(manager.free_slots(), 4);
+        assert!(manager.plan(0x1000, 16).is_ok());
+        assert!(matches!(
+            manager.plan(0x1000, 20),
+            Err(WatchpointError::InsufficientSlots {
+                required: 5,
+                available: 4,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fallback_keeps_reads() {
+        let mut manager = WatchpointManager::with_limits(0, 8);
+        let mut target = bindings::SBTarget::new().wrap();
+        let mut process = bindings::SBProcess::new().wrap();
+        for write in [false, true] {
+            assert!(matches!(
+                manager.watch_with_fallback(&mut target, &mut process, 0x1000, 8, true, write),
+                Err(WatchpointError::SoftwareReadWatch {
+                    address: 0x1000,
+                    size: 8
+                })
+            ));
+        }
+        assert!(!manager.has_software_watches());
+    }
+
+    #[test]
+    fn test_software_without_process() {
+        let mut manager = WatchpointManager::with_limits(0, 8);
+        let handle = manager.insert(Watch::Software(SoftwareWatch {
+            address: 0x1000,
+            snapshot: vec![1, 2, 3, 4],
+        }));
+        let mut process = bindings::SBProcess::new().wrap();
+        // A process that isn't stopped has no hits instead of failing to read.
+        assert!(manager.check_stopped(&mut process, 0).unwrap().is_empty());
+        // A failed read keeps the snapshot to compare against.
+        assert!(manager.check_software(&mut process, 0).is_err());
+        assert!(matches!(
+            manager.watches.get(&handle),
+            Some(Watch::Software(w)) if w.snapshot == vec![1, 2, 3, 4]
+        ));
+    }
+}
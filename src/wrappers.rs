@@ -1,5 +1,6 @@
 use crate::api::ffi::lldb as bindings;
 use crate::autocxx::prelude::*;
+use crate::events::EventMask;
 use std::pin::Pin;
 
 // We could really benefit from:
@@ -10,8 +11,8 @@ use std::pin::Pin;
 type Carrier<T> = Pin<Box<T>>;
 
 // Some more type aliases
-type SBResult<T> = Result<T, Wrapped<bindings::SBError>>;
-type Address = u64;
+pub(crate) type SBResult<T> = Result<T, Wrapped<bindings::SBError>>;
+type Addr = u64;
 
 // We need a wrapper type we own, such that we can implement external traits such as std::fmt::Debug
 // But our traits with convenience methods that are the safe API are implemented for anything with
@@ -87,6 +88,15 @@ unsafe fn as_mut_ref<T: ?Sized>(z: &T) -> Pin<&mut T> {
     Pin::new_unchecked(mut_self)
 }
 
+/// Convert a C string obtained from the API into an owned string, returns None for nullptr.
+pub(crate) fn to_string_opt(p: *const std::os::raw::c_char) -> Option<String> {
+    if p.is_null() {
+        return None;
+    }
+    let z = unsafe { std::ffi::CStr::from_ptr(p) };
+    Some(z.to_string_lossy().into_owned())
+}
+
 /// Macro to implement pin_mut() for our types in UniquePtr<T> and Pin<Box<T>>
 macro_rules! handle_box_and_uniqueptr {
     ($t:ty) => {
@@ -119,9 +129,222 @@ macro_rules! handle_box_and_uniqueptr {
 // Actual implementations now follow.
 
 handle_box_and_uniqueptr!(bindings::SBDebugger);
-pub trait Debugger: autocxx::PinMut<bindings::SBDebugger> {}
+pub trait Debugger: autocxx::PinMut<bindings::SBDebugger> {
+    // lldb::SBListener GetListener();
+    /// The debugger's default listener, this receives the events of processes created through it.
+    fn get_listener(&mut self) -> Wrapped<bindings::SBListener> {
+        self.pin_mut().GetListener().wrap()
+    }
+
+    // lldb::SBTarget CreateTarget(const char *filename, const char *target_triple, const char *platform_name, bool add_dependent_modules, lldb::SBError &error);
+    /// Create a target for the executable at this path.
+    fn create_target(&mut self, path: &str) -> SBResult<Wrapped<bindings::SBTarget>> {
+        let path = std::ffi::CString::new(path).expect("no null bytes expected");
+        let mut e = bindings::SBError::new().wrap();
+        let res = unsafe {
+            self.pin_mut().CreateTarget(
+                path.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                true,
+                e.pin_mut(),
+            )
+        }
+        .wrap();
+        if e.is_success() {
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // bool DeleteTarget(lldb::SBTarget &target);
+    fn delete_target<T: Target>(&mut self, target: &mut T) -> bool {
+        self.pin_mut().DeleteTarget(target.pin_mut())
+    }
+
+    /// Run a command through the command interpreter, returns the output or the error output.
+    fn handle_command(&mut self, command: &str) -> Result<String, String> {
+        let command = std::ffi::CString::new(command).expect("no null bytes expected");
+        let mut ci = self.pin_mut().GetCommandInterpreter().within_box();
+        let mut result = bindings::SBCommandReturnObject::new().within_box();
+        unsafe {
+            ci.as_mut()
+                .HandleCommand(command.as_ptr(), result.as_mut(), false)
+        };
+        if result.as_mut().Succeeded() {
+            return Ok(to_string_opt(result.as_mut().GetOutput()).unwrap_or_default());
+        }
+        Err(to_string_opt(result.as_mut().GetError()).unwrap_or_default())
+    }
+
+    /// The broadcaster of the command interpreter.
+    fn command_interpreter_broadcaster(&mut self) -> Wrapped<bindings::SBBroadcaster> {
+        let mut ci = self.pin_mut().GetCommandInterpreter().within_box();
+        ci.as_mut().GetBroadcaster().wrap()
+    }
+}
 impl<T> Debugger for T where T: autocxx::PinMut<bindings::SBDebugger> {}
 
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBBroadcaster.h
+handle_box_and_uniqueptr!(bindings::SBBroadcaster);
+pub trait Broadcaster: autocxx::PinMut<bindings::SBBroadcaster> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    fn get_name(&self) -> String {
+        to_string_opt(self.as_ref().GetName()).unwrap_or_default()
+    }
+}
+impl<T> Broadcaster for T where T: autocxx::PinMut<bindings::SBBroadcaster> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBListener.h
+handle_box_and_uniqueptr!(bindings::SBListener);
+pub trait Listener: autocxx::PinMut<bindings::SBListener> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // uint32_t StartListeningForEvents(const lldb::SBBroadcaster &broadcaster, uint32_t event_mask);
+    /// Start listening for events from the broadcaster, returns the bits that were acquired.
+    fn start_listening_for_events<B: Broadcaster, M: EventMask>(
+        &mut self,
+        broadcaster: &B,
+        mask: M,
+    ) -> M {
+        let bits = self
+            .pin_mut()
+            .StartListeningForEvents(broadcaster.as_ref(), mask.to_bits());
+        M::from_bits_lossy(bits)
+    }
+
+    // bool StopListeningForEvents(const lldb::SBBroadcaster &broadcaster, uint32_t event_mask);
+    fn stop_listening_for_events<B: Broadcaster, M: EventMask>(
+        &mut self,
+        broadcaster: &B,
+        mask: M,
+    ) -> bool {
+        self.pin_mut()
+            .StopListeningForEvents(broadcaster.as_ref(), mask.to_bits())
+    }
+
+    // bool WaitForEvent(uint32_t num_seconds, lldb::SBEvent &event);
+    /// Block until an event arrives or the timeout expires, the timeout is rounded up to whole
+    /// seconds. A zero timeout doesn't block and only takes an event that is already queued.
+    fn wait_for_event(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Option<Wrapped<bindings::SBEvent>> {
+        // LLDB asserts that the number of seconds isn't 0.
+        if timeout.is_zero() {
+            return self.get_next_event();
+        }
+        let mut event = bindings::SBEvent::new().wrap();
+        // https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/source/API/SBListener.cpp#L142-L165
+        // returns true only if there was an event to get, always populates the event.
+        if self
+            .pin_mut()
+            .WaitForEvent(timeout_seconds(timeout), event.pin_mut())
+        {
+            return Some(event);
+        }
+        None
+    }
+
+    // bool WaitForEventForBroadcaster(uint32_t num_seconds, const lldb::SBBroadcaster &broadcaster, lldb::SBEvent &sb_event);
+    /// Like [`Listener::wait_for_event`], but only for events from this broadcaster.
+    fn wait_for_event_for_broadcaster<B: Broadcaster>(
+        &mut self,
+        timeout: std::time::Duration,
+        broadcaster: &B,
+    ) -> Option<Wrapped<bindings::SBEvent>> {
+        if timeout.is_zero() {
+            return self.get_next_event_for_broadcaster(broadcaster);
+        }
+        let mut event = bindings::SBEvent::new().wrap();
+        if self.pin_mut().WaitForEventForBroadcaster(
+            timeout_seconds(timeout),
+            broadcaster.as_ref(),
+            event.pin_mut(),
+        ) {
+            return Some(event);
+        }
+        None
+    }
+
+    // bool PeekAtNextEvent(lldb::SBEvent &sb_event);
+    /// Look at the next event without removing it from the queue.
+    fn peek(&mut self) -> Option<Wrapped<bindings::SBEvent>> {
+        let mut event = bindings::SBEvent::new().wrap();
+        if self.pin_mut().PeekAtNextEvent(event.pin_mut()) {
+            return Some(event);
+        }
+        None
+    }
+
+    // bool GetNextEvent(lldb::SBEvent &sb_event);
+    /// Obtain the next event if there is one, without blocking.
+    fn get_next_event(&mut self) -> Option<Wrapped<bindings::SBEvent>> {
+        let mut event = bindings::SBEvent::new().wrap();
+        if self.pin_mut().GetNextEvent(event.pin_mut()) {
+            return Some(event);
+        }
+        None
+    }
+
+    // bool GetNextEventForBroadcaster(const lldb::SBBroadcaster &broadcaster, lldb::SBEvent &sb_event);
+    /// Like [`Listener::get_next_event`], but only for events from this broadcaster.
+    fn get_next_event_for_broadcaster<B: Broadcaster>(
+        &mut self,
+        broadcaster: &B,
+    ) -> Option<Wrapped<bindings::SBEvent>> {
+        let mut event = bindings::SBEvent::new().wrap();
+        if self
+            .pin_mut()
+            .GetNextEventForBroadcaster(broadcaster.as_ref(), event.pin_mut())
+        {
+            return Some(event);
+        }
+        None
+    }
+
+    /// Iterate over all events that are currently queued, without blocking.
+    fn drain(&mut self) -> Drain<'_, Self> {
+        Drain { listener: self }
+    }
+}
+impl<T> Listener for T where T: autocxx::PinMut<bindings::SBListener> {}
+
+impl Wrapped<bindings::SBListener> {
+    /// Create a dedicated listener, separate from the debugger's default listener.
+    pub fn with_name(name: &str) -> Self {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        unsafe { bindings::SBListener::new1(name.as_ptr()) }.wrap()
+    }
+}
+
+/// Convert a timeout to whole seconds, rounding up such that we never return early. Zero timeouts
+/// must not be passed to LLDB, the listener takes the non-blocking path for those.
+fn timeout_seconds(timeout: std::time::Duration) -> u32 {
+    let mut secs = timeout.as_secs();
+    if timeout.subsec_nanos() != 0 {
+        secs += 1;
+    }
+    // UINT32_MAX means wait forever, which is what the caller wants for such a large value anyway.
+    secs.min(u32::MAX as u64) as u32
+}
+
+/// Iterator over the queued events of a listener.
+pub struct Drain<'a, L: ?Sized> {
+    listener: &'a mut L,
+}
+impl<'a, L: Listener + ?Sized> Iterator for Drain<'a, L> {
+    type Item = Wrapped<bindings::SBEvent>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.listener.get_next_event()
+    }
+}
+
 // https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBProcess.h
 handle_box_and_uniqueptr!(bindings::SBProcess);
 pub trait Process: autocxx::PinMut<bindings::SBProcess> {
@@ -130,7 +353,7 @@ pub trait Process: autocxx::PinMut<bindings::SBProcess> {
     }
 
     // size_t ReadMemory(addr_t addr, void *buf, size_t size, lldb::SBError &error);
-    fn read_memory(&mut self, address: Address, size: usize) -> SBResult<Vec<u8>> {
+    fn read_memory(&mut self, address: Addr, size: usize) -> SBResult<Vec<u8>> {
         let mut res = Vec::<u8>::new();
         res.resize(size, 0);
         let mut e = bindings::SBError::new().wrap();
@@ -148,70 +371,1794 @@ pub trait Process: autocxx::PinMut<bindings::SBProcess> {
     // size_t WriteMemory(addr_t addr, const void *buf, size_t size,
     // lldb::SBError &error);
 
-    // size_t ReadCStringFromMemory(addr_t addr, void *buf, size_t size, lldb::SBError &error);
-    fn read_cstring_from_memory(
-        &mut self,
-        address: Address,
-        size: usize,
-    ) -> SBResult<std::ffi::CString> {
-        let mut res = Vec::<u8>::new();
-        res.resize(size, 0);
+    // size_t ReadCStringFromMemory(addr_t addr, void *buf, size_t size, lldb::SBError &error);
+    fn read_cstring_from_memory(
+        &mut self,
+        address: Addr,
+        size: usize,
+    ) -> SBResult<std::ffi::CString> {
+        let mut res = Vec::<u8>::new();
+        res.resize(size, 0);
+        let mut e = bindings::SBError::new().wrap();
+        let ret = unsafe {
+            self.pin_mut()
+                .ReadCStringFromMemory(address, res.as_mut_ptr() as _, size, e.pin_mut())
+        };
+        if e.is_success() {
+            let relevant = &res[0..ret];
+            let res = std::ffi::CString::new(relevant).expect("found null byte in string");
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // uint32_t GetNumThreads();
+    fn get_num_threads(&mut self) -> u32 {
+        self.pin_mut().GetNumThreads()
+    }
+
+    /// Return all threads of this process.
+    fn threads(&mut self) -> Vec<Wrapped<bindings::SBThread>> {
+        (0..self.get_num_threads() as usize)
+            .map(|i| self.thread(i))
+            .collect()
+    }
+
+    // lldb::SBBroadcaster GetBroadcaster() const;
+    fn broadcaster(&self) -> Wrapped<bindings::SBBroadcaster> {
+        self.as_ref().GetBroadcaster().wrap()
+    }
+
+    // lldb::pid_t GetProcessID();
+    fn get_process_id(&mut self) -> u64 {
+        self.pin_mut().GetProcessID()
+    }
+
+    // lldb::SBTarget GetTarget() const;
+    fn target(&self) -> Wrapped<bindings::SBTarget> {
+        self.as_ref().GetTarget().wrap()
+    }
+
+    // lldb::StateType GetState();
+    fn get_state(&mut self) -> bindings::StateType {
+        self.pin_mut().GetState()
+    }
+
+    // lldb::SBError Continue();
+    fn resume(&mut self) -> SBResult<()> {
+        let e = self.pin_mut().Continue().wrap();
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    // lldb::SBError Stop();
+    fn stop(&mut self) -> SBResult<()> {
+        let e = self.pin_mut().Stop().wrap();
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    // lldb::SBError Detach();
+    fn detach(&mut self) -> SBResult<()> {
+        let e = self.pin_mut().Detach().wrap();
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    // lldb::SBError Kill();
+    fn kill(&mut self) -> SBResult<()> {
+        let e = self.pin_mut().Kill().wrap();
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    // int GetExitStatus();
+    fn get_exit_status(&mut self) -> i32 {
+        self.pin_mut().GetExitStatus().0
+    }
+
+    // const char *GetExitDescription();
+    fn get_exit_description(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetExitDescription())
+    }
+
+    // size_t GetSTDOUT(char *dst, size_t dst_len) const;
+    /// Read buffered output of the process into the slice, returns the number of bytes read.
+    fn get_stdout(&self, buffer: &mut [u8]) -> usize {
+        unsafe {
+            self.as_ref()
+                .GetSTDOUT(buffer.as_mut_ptr() as _, buffer.len())
+        }
+    }
+
+    // size_t GetSTDERR(char *dst, size_t dst_len) const;
+    fn get_stderr(&self, buffer: &mut [u8]) -> usize {
+        unsafe {
+            self.as_ref()
+                .GetSTDERR(buffer.as_mut_ptr() as _, buffer.len())
+        }
+    }
+
+    // size_t PutSTDIN(const char *src, size_t src_len);
+    /// Write to the stdin of the process, returns the number of bytes written.
+    fn put_stdin(&mut self, buffer: &[u8]) -> usize {
+        unsafe { self.pin_mut().PutSTDIN(buffer.as_ptr() as _, buffer.len()) }
+    }
+
+    /// Reader for the stdout of a process whose output goes to the debugger.
+    fn stdout_reader(&self) -> crate::process_io::OutputReader {
+        crate::process_io::stdout_reader(bindings::SBProcess::new1(self.as_ref()).wrap())
+    }
+
+    fn stderr_reader(&self) -> crate::process_io::OutputReader {
+        crate::process_io::stderr_reader(bindings::SBProcess::new1(self.as_ref()).wrap())
+    }
+
+    fn stdin_writer(&self) -> crate::process_io::InputWriter {
+        crate::process_io::stdin_writer(bindings::SBProcess::new1(self.as_ref()).wrap())
+    }
+
+    // uint32_t GetNumSupportedHardwareWatchpoints(lldb::SBError &error) const;
+    fn get_num_supported_hardware_watchpoints(&mut self) -> SBResult<u32> {
+        let mut e = bindings::SBError::new().wrap();
+        let ret = self
+            .pin_mut()
+            .GetNumSupportedHardwareWatchpoints(e.pin_mut());
+        if e.is_success() {
+            return Ok(ret);
+        }
+        Err(e)
+    }
+}
+// This works:
+impl<T: autocxx::PinMut<bindings::SBProcess>> Process for T {}
+// But so does this:
+// impl<T> Process for T where T: autocxx::PinMut<bindings::SBProcess> {}
+// And these three:
+// impl Process for UniquePtr<bindings::SBProcess>  {}
+// impl Process for Wrapped<bindings::SBProcess>  {}
+// impl Process for Pin<Box<bindings::SBProcess>>  {}
+// Not sure which one is superior atm.
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBTarget.h
+handle_box_and_uniqueptr!(bindings::SBTarget);
+pub trait Target: autocxx::PinMut<bindings::SBTarget> {
+    // lldb::SBWatchpoint WatchAddress(lldb::addr_t addr, size_t size, bool read, bool write, SBError &error);
+    /// Set a watch point at the specified location and size, check
+    /// [`Process::get_num_supported_hardware_watchpoints`] to confirm there's hardware watchpoints.
+    /// Size cannot be an arbitrary value either, for example 96 fails, possibly only register
+    /// widths?
+    /// I ran into 'sending gdb watchpoint failed' errors, which may have been caused by trying to
+    /// set a 8 byte wide watchpoint on a 32 bits process, using 4 byte width worked.
+    /// The [`crate::watchpoints::WatchpointManager`] takes care of splitting ranges into chunks
+    /// that the hardware accepts.
+    fn watch_address(
+        &mut self,
+        address: u64,
+        size: usize,
+        read: bool,
+        write: bool,
+    ) -> SBResult<Wrapped<bindings::SBWatchpoint>> {
+        let mut e = bindings::SBError::new().wrap();
+        let res = self
+            .pin_mut()
+            .WatchAddress(address, size, read, write, e.pin_mut())
+            .wrap();
+        if e.is_success() {
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // fn delete_watchpoint(&mut self,
+    fn delete_watchpoint(&mut self, watchpoint_id: WatchpointId) -> bool {
+        self.pin_mut().DeleteWatchpoint(watchpoint_id.0)
+    }
+
+    // lldb::SBBroadcaster GetBroadcaster() const;
+    fn broadcaster(&self) -> Wrapped<bindings::SBBroadcaster> {
+        self.as_ref().GetBroadcaster().wrap()
+    }
+
+    // lldb::SBProcess GetProcess();
+    fn process(&mut self) -> Wrapped<bindings::SBProcess> {
+        self.pin_mut().GetProcess().wrap()
+    }
+
+    // lldb::SBType FindFirstType(const char *type);
+    fn find_first_type(&mut self, name: &str) -> SBResult<Wrapped<bindings::SBType>> {
+        let name_c = std::ffi::CString::new(name).expect("no null bytes expected");
+        let res = unsafe { self.pin_mut().FindFirstType(name_c.as_ptr()) }.wrap();
+        if !res.is_valid() {
+            return Err(Wrapped::<bindings::SBError>::from_message(&format!(
+                "no type named {name}"
+            )));
+        }
+        Ok(res)
+    }
+
+    // const char *GetTriple();
+    fn get_triple(&mut self) -> String {
+        to_string_opt(self.pin_mut().GetTriple()).unwrap_or_default()
+    }
+
+    // lldb::SBBreakpoint BreakpointCreateByAddress(addr_t address);
+    fn breakpoint_create_by_address(&mut self, address: Addr) -> Wrapped<bindings::SBBreakpoint> {
+        self.pin_mut().BreakpointCreateByAddress(address).wrap()
+    }
+
+    // lldb::SBBreakpoint BreakpointCreateByName(const char *symbol_name, const char *module_name = nullptr);
+    /// Create a breakpoint on a symbol name, this resolves in all modules, also ones loaded later.
+    fn breakpoint_create_by_name(&mut self, symbol: &str) -> Wrapped<bindings::SBBreakpoint> {
+        let name = std::ffi::CString::new(symbol).expect("no null bytes expected");
+        unsafe {
+            self.pin_mut()
+                .BreakpointCreateByName(name.as_ptr(), std::ptr::null())
+        }
+        .wrap()
+    }
+
+    // lldb::SBBreakpoint BreakpointCreateBySBAddress(SBAddress &address);
+    /// Create a breakpoint on a section relative address, this follows the module if it gets
+    /// loaded at another address.
+    fn breakpoint_create_by_sb_address<A: Address>(
+        &mut self,
+        address: &mut A,
+    ) -> Wrapped<bindings::SBBreakpoint> {
+        self.pin_mut()
+            .BreakpointCreateBySBAddress(address.pin_mut())
+            .wrap()
+    }
+
+    // bool BreakpointDelete(break_id_t break_id);
+    fn delete_breakpoint(&mut self, breakpoint_id: BreakpointId) -> bool {
+        self.pin_mut().BreakpointDelete(breakpoint_id.0)
+    }
+
+    // lldb::SBBreakpoint FindBreakpointByID(break_id_t break_id);
+    /// Find a breakpoint by its id, returns None if no such breakpoint exists.
+    fn find_breakpoint_by_id(
+        &mut self,
+        breakpoint_id: BreakpointId,
+    ) -> Option<Wrapped<bindings::SBBreakpoint>> {
+        let res = self.pin_mut().FindBreakpointByID(breakpoint_id.0).wrap();
+        if res.is_valid() {
+            return Some(res);
+        }
+        None
+    }
+
+    // uint32_t GetAddressByteSize();
+    /// Size of a pointer in the target, 4 for 32 bits processes, 8 for 64 bits.
+    fn get_address_byte_size(&mut self) -> u32 {
+        self.pin_mut().GetAddressByteSize()
+    }
+
+    // lldb::SBInstructionList ReadInstructions(lldb::SBAddress base_addr, uint32_t count);
+    /// Disassemble `count` instructions starting at the provided load address.
+    fn read_instructions(
+        &mut self,
+        address: Addr,
+        count: u32,
+    ) -> Wrapped<bindings::SBInstructionList> {
+        let base = self.pin_mut().ResolveLoadAddress(address).within_box();
+        self.pin_mut().ReadInstructions(base, count).wrap()
+    }
+
+    // lldb::SBAddress ResolveFileAddress(lldb::addr_t file_addr);
+    /// Address from a virtual address in the file image, usable before the process is launched.
+    fn resolve_file_address(&mut self, file_address: Addr) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().ResolveFileAddress(file_address).wrap()
+    }
+
+    // lldb::SBAddress ResolveLoadAddress(lldb::addr_t vm_addr);
+    fn resolve_load_address(&mut self, load_address: Addr) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().ResolveLoadAddress(load_address).wrap()
+    }
+
+    // lldb::SBSymbolContext ResolveSymbolContextForAddress(const lldb::SBAddress &addr, uint32_t resolve_scope);
+    /// The symbol context for the address, `resolve_scope` is a mask of `lldb::SymbolContextItem`.
+    fn resolve_symbol_context_for_address<A: Address>(
+        &mut self,
+        address: &A,
+        resolve_scope: u32,
+    ) -> Wrapped<bindings::SBSymbolContext> {
+        self.pin_mut()
+            .ResolveSymbolContextForAddress(address.as_ref(), resolve_scope)
+            .wrap()
+    }
+
+    /// The module, symbol, function and source location of a load address, see
+    /// [`crate::symbolication::Symbolicator`] for symbolicating many addresses.
+    fn symbolicate(&mut self, address: Addr) -> crate::symbolication::SymbolInfo
+    where
+        Self: Sized,
+    {
+        crate::symbolication::symbolicate(self, address)
+    }
+
+    // size_t ReadMemory(const SBAddress addr, void *buf, size_t size, lldb::SBError &error);
+    /// Read memory at the address, from the file image if there is no live process.
+    fn read_memory<A: autocxx::PinMut<bindings::SBAddress>>(
+        &mut self,
+        address: &A,
+        size: usize,
+    ) -> SBResult<Vec<u8>> {
+        let mut res = vec![0u8; size];
+        let mut e = bindings::SBError::new().wrap();
+        let ret = unsafe {
+            self.pin_mut()
+                .ReadMemory(address.as_ref(), res.as_mut_ptr() as _, size, e.pin_mut())
+        };
+        if e.is_success() {
+            res.truncate(ret); // clip to whatever was read
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // lldb::SBValue EvaluateExpression(const char *expr, const SBExpressionOptions &options);
+    /// Evaluate an expression without a frame, for globals and static properties like the size of
+    /// a type.
+    fn evaluate_expression(
+        &mut self,
+        expr: &str,
+        options: &crate::expression::ExpressionOptions,
+    ) -> Result<Wrapped<bindings::SBValue>, crate::expression::ExpressionError> {
+        let expr = std::ffi::CString::new(expr).expect("no null bytes expected");
+        let sb_options = options.to_sb();
+        let res = unsafe {
+            self.pin_mut()
+                .EvaluateExpression1(expr.as_ptr(), sb_options.as_ref())
+        }
+        .wrap();
+        crate::expression::check_result(res)
+    }
+
+    // lldb::SBValueList FindGlobalVariables(const char *name, uint32_t max_matches, MatchType matchtype);
+    /// Global variables matching the name, or the regular expression if `regex` is set.
+    fn find_global_variables(
+        &mut self,
+        name: &str,
+        max_matches: u32,
+        regex: bool,
+    ) -> Vec<Wrapped<bindings::SBValue>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        let match_type = if regex {
+            bindings::MatchType::eMatchTypeRegex
+        } else {
+            bindings::MatchType::eMatchTypeNormal
+        };
+        unsafe {
+            self.pin_mut()
+                .FindGlobalVariables1(name.as_ptr(), max_matches, match_type)
+        }
+        .wrap()
+        .values()
+    }
+
+    // uint32_t GetNumModules() const;
+    fn get_num_modules(&self) -> u32 {
+        self.as_ref().GetNumModules()
+    }
+
+    // lldb::SBModule GetModuleAtIndex(uint32_t idx);
+    fn module_at_index(&mut self, index: u32) -> Wrapped<bindings::SBModule> {
+        self.pin_mut().GetModuleAtIndex(index).wrap()
+    }
+
+    /// All modules of the target, the executable first.
+    fn modules(&mut self) -> Vec<Wrapped<bindings::SBModule>> {
+        (0..self.get_num_modules())
+            .map(|i| self.module_at_index(i))
+            .collect()
+    }
+
+    // lldb::SBModule FindModule(const lldb::SBFileSpec &file_spec);
+    /// Find a module by file, a file spec without a directory matches on the file name only.
+    fn find_module<F: FileSpec>(&mut self, file: &F) -> Option<Wrapped<bindings::SBModule>> {
+        let res = self.pin_mut().FindModule(file.as_ref()).wrap();
+        if res.is_valid() {
+            return Some(res);
+        }
+        None
+    }
+
+    /// Find a module by path or file name, like `kernel32.dll`.
+    fn find_module_by_name(&mut self, name: &str) -> Option<Wrapped<bindings::SBModule>> {
+        self.find_module(&Wrapped::<bindings::SBFileSpec>::from_path(name))
+    }
+
+    // lldb::SBModule AddModule(const char *path, const char *triple, const char *uuid);
+    /// Add the object file at this path to the target, the triple and UUID default to those of the
+    /// file.
+    fn add_module(
+        &mut self,
+        path: &str,
+        triple: Option<&str>,
+        uuid: Option<&str>,
+    ) -> SBResult<Wrapped<bindings::SBModule>> {
+        let to_c = |s: &str| std::ffi::CString::new(s).expect("no null bytes expected");
+        let path_c = to_c(path);
+        let triple = triple.map(to_c);
+        let uuid = uuid.map(to_c);
+        let as_ptr =
+            |s: &Option<std::ffi::CString>| s.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+        let res = unsafe {
+            self.pin_mut()
+                .AddModule1(path_c.as_ptr(), as_ptr(&triple), as_ptr(&uuid))
+        }
+        .wrap();
+        if !res.is_valid() {
+            return Err(Wrapped::<bindings::SBError>::from_message(&format!(
+                "failed to add module {path}"
+            )));
+        }
+        Ok(res)
+    }
+
+    // lldb::SBModule AddModule(const SBModuleSpec &module_spec);
+    fn add_module_spec<S: ModuleSpec>(
+        &mut self,
+        spec: &S,
+    ) -> SBResult<Wrapped<bindings::SBModule>> {
+        let res = self.pin_mut().AddModule3(spec.as_ref()).wrap();
+        if !res.is_valid() {
+            return Err(Wrapped::<bindings::SBError>::from_message(
+                "failed to add module from spec",
+            ));
+        }
+        Ok(res)
+    }
+
+    // bool RemoveModule(lldb::SBModule module);
+    fn remove_module<M: Module>(&mut self, module: &M) -> bool {
+        self.pin_mut().RemoveModule(module.as_ref())
+    }
+
+    // lldb::SBSymbolContextList FindGlobalFunctions(const char *name, uint32_t max_matches, MatchType matchtype);
+    /// Functions in all modules matching the name, as a plain name, regex or prefix.
+    fn find_functions(
+        &mut self,
+        name: &str,
+        match_type: bindings::MatchType,
+    ) -> Vec<Wrapped<bindings::SBSymbolContext>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        unsafe {
+            self.pin_mut()
+                .FindGlobalFunctions(name.as_ptr(), u32::MAX, match_type)
+        }
+        .wrap()
+        .contexts()
+    }
+
+    // lldb::SBSymbolContextList FindSymbols(const char *name, lldb::SymbolType type = eSymbolTypeAny);
+    /// Symbols in all modules with this exact name, also finds symbols without debug info.
+    fn find_symbols(
+        &mut self,
+        name: &str,
+        symbol_type: bindings::SymbolType,
+    ) -> Vec<Wrapped<bindings::SBSymbolContext>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        unsafe { self.pin_mut().FindSymbols(name.as_ptr(), symbol_type) }
+            .wrap()
+            .contexts()
+    }
+
+    /// The start addresses of the code for a source line, in all modules. The file is matched on
+    /// its full path if it has a directory, otherwise on the file name.
+    fn addresses_for_line(&mut self, file: &str, line: u32) -> Vec<Wrapped<bindings::SBAddress>> {
+        let mut spec = Wrapped::<bindings::SBFileSpec>::from_path(file);
+        let mut seen = std::collections::HashSet::new();
+        let mut res = vec![];
+        for (i, mut module) in self.modules().into_iter().enumerate() {
+            for cu in module.compile_units() {
+                let mut index = cu.find_line_entry_index(0, line, &mut spec, true);
+                while let Some(found) = index {
+                    let address = cu.line_entry_at_index(found).get_start_address();
+                    // A line can have multiple entries at the same address, like for columns.
+                    if let Some(file_address) = address.get_file_address() {
+                        if seen.insert((i, file_address)) {
+                            res.push(address);
+                        }
+                    }
+                    index = cu.find_line_entry_index(found + 1, line, &mut spec, true);
+                }
+            }
+        }
+        res
+    }
+
+    // lldb::SBSourceManager GetSourceManager();
+    fn source_manager(&mut self) -> Wrapped<bindings::SBSourceManager> {
+        self.pin_mut().GetSourceManager().wrap()
+    }
+
+    /// The start of the symbol with this name in one module, relative to that module such that it
+    /// stays valid wherever the module is loaded.
+    fn resolve_symbol(
+        &mut self,
+        module: &str,
+        name: &str,
+    ) -> SBResult<Wrapped<bindings::SBAddress>> {
+        let mut m = self.find_module_by_name(module).ok_or_else(|| {
+            Wrapped::<bindings::SBError>::from_message(&format!("no module {module}"))
+        })?;
+        let mut symbol = m
+            .find_symbol(name, bindings::SymbolType::eSymbolTypeAny)
+            .ok_or_else(|| {
+                Wrapped::<bindings::SBError>::from_message(&format!("no symbol {name} in {module}"))
+            })?;
+        Ok(symbol.get_start_address())
+    }
+
+    // uint32_t GetNumWatchpoints() const;
+    fn get_num_watchpoints(&self) -> u32 {
+        self.as_ref().GetNumWatchpoints()
+    }
+
+    // lldb::SBWatchpoint GetWatchpointAtIndex(uint32_t idx) const;
+    fn watchpoint_at_index(&self, index: u32) -> Wrapped<bindings::SBWatchpoint> {
+        self.as_ref().GetWatchpointAtIndex(index).wrap()
+    }
+
+    /// Return all watchpoints currently set on this target.
+    fn watchpoints(&self) -> Vec<Wrapped<bindings::SBWatchpoint>> {
+        (0..self.get_num_watchpoints())
+            .map(|i| self.watchpoint_at_index(i))
+            .collect()
+    }
+
+    // lldb::SBWatchpoint FindWatchpointByID(lldb::watch_id_t watch_id);
+    /// Find a watchpoint by its id, returns None if no such watchpoint exists.
+    fn find_watchpoint_by_id(
+        &mut self,
+        watchpoint_id: WatchpointId,
+    ) -> Option<Wrapped<bindings::SBWatchpoint>> {
+        let res = self.pin_mut().FindWatchpointByID(watchpoint_id.0).wrap();
+        if res.is_valid() {
+            return Some(res);
+        }
+        None
+    }
+}
+impl<T> Target for T where T: autocxx::PinMut<bindings::SBTarget> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBThread.h
+handle_box_and_uniqueptr!(bindings::SBThread);
+pub trait Thread: autocxx::PinMut<bindings::SBThread> {
+    fn frame(&mut self, id: u32) -> Wrapped<bindings::SBFrame> {
+        self.pin_mut().GetFrameAtIndex(id).wrap()
+    }
+
+    // uint32_t GetNumFrames();
+    fn get_num_frames(&mut self) -> u32 {
+        self.pin_mut().GetNumFrames()
+    }
+
+    // lldb::SBBroadcaster GetBroadcaster() const;
+    fn broadcaster(&self) -> Wrapped<bindings::SBBroadcaster> {
+        self.as_ref().GetBroadcaster().wrap()
+    }
+
+    // lldb::SBProcess GetProcess();
+    fn process(&mut self) -> Wrapped<bindings::SBProcess> {
+        self.pin_mut().GetProcess().wrap()
+    }
+
+    // lldb::tid_t GetThreadID() const;
+    fn get_thread_id(&self) -> u64 {
+        self.as_ref().GetThreadID()
+    }
+
+    // lldb::StopReason GetStopReason();
+    fn get_stop_reason(&mut self) -> bindings::StopReason {
+        self.pin_mut().GetStopReason()
+    }
+
+    // size_t GetStopReasonDataCount();
+    fn get_stop_reason_data_count(&mut self) -> usize {
+        self.pin_mut().GetStopReasonDataCount()
+    }
+
+    // uint64_t GetStopReasonDataAtIndex(uint32_t idx);
+    fn get_stop_reason_data_at_index(&mut self, index: u32) -> u64 {
+        self.pin_mut().GetStopReasonDataAtIndex(index)
+    }
+
+    /// The breakpoints that caused this thread to stop, empty if it didn't stop for a breakpoint.
+    fn stop_breakpoint_ids(&mut self) -> Vec<BreakpointId> {
+        if self.get_stop_reason() != bindings::StopReason::eStopReasonBreakpoint {
+            return vec![];
+        }
+        // Data is pairs of breakpoint id and location id.
+        let count = self.get_stop_reason_data_count() as u32;
+        (0..count)
+            .step_by(2)
+            .map(|i| BreakpointId(self.get_stop_reason_data_at_index(i) as i32))
+            .collect()
+    }
+
+    /// The watchpoint that caused this thread to stop.
+    fn stop_watchpoint_id(&mut self) -> Option<WatchpointId> {
+        if self.get_stop_reason() != bindings::StopReason::eStopReasonWatchpoint {
+            return None;
+        }
+        Some(WatchpointId(self.get_stop_reason_data_at_index(0) as i32))
+    }
+
+    /// The signal that caused this thread to stop.
+    fn stop_signal(&mut self) -> Option<i32> {
+        if self.get_stop_reason() != bindings::StopReason::eStopReasonSignal {
+            return None;
+        }
+        Some(self.get_stop_reason_data_at_index(0) as i32)
+    }
+
+    // size_t GetStopDescription(char *dst, size_t dst_len);
+    fn get_stop_description(&mut self) -> String {
+        let mut res = vec![0u8; 1024];
+        let len = unsafe {
+            self.pin_mut()
+                .GetStopDescription(res.as_mut_ptr() as _, res.len())
+        };
+        // The length may include the terminator.
+        res.truncate(len.min(res.len()));
+        String::from_utf8_lossy(&res)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    // void StepInstruction(bool step_over, SBError &error);
+    /// Step a single instruction, in asynchronous mode this returns before the step completed.
+    fn step_instruction(&mut self, step_over: bool) -> SBResult<()> {
+        let mut e = bindings::SBError::new().wrap();
+        self.pin_mut().StepInstruction1(step_over, e.pin_mut());
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    // void RunToAddress(lldb::addr_t addr, SBError &error);
+    fn run_to_address(&mut self, address: Addr) -> SBResult<()> {
+        let mut e = bindings::SBError::new().wrap();
+        self.pin_mut().RunToAddress1(address, e.pin_mut());
+        if e.is_success() {
+            return Ok(());
+        }
+        Err(e)
+    }
+
+    /// The call stack of this thread, innermost frame first, up to `max_frames` frames.
+    fn backtrace(&mut self, max_frames: usize) -> crate::backtrace::Backtrace
+    where
+        Self: Sized,
+    {
+        crate::backtrace::capture(self, 0, max_frames)
+    }
+}
+impl<T> Thread for T where T: autocxx::PinMut<bindings::SBThread> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBFrame.h
+handle_box_and_uniqueptr!(bindings::SBFrame);
+pub trait Frame: autocxx::PinMut<bindings::SBFrame> {
+    // lldb::addr_t GetPC() const;
+    fn get_pc(&self) -> Addr {
+        self.as_ref().GetPC()
+    }
+
+    // lldb::addr_t GetSP() const;
+    fn get_sp(&self) -> Addr {
+        self.as_ref().GetSP()
+    }
+
+    // lldb::SBThread GetThread() const;
+    fn thread(&self) -> Wrapped<bindings::SBThread> {
+        self.as_ref().GetThread().wrap()
+    }
+
+    // const char *GetFunctionName();
+    fn get_function_name(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetFunctionName())
+    }
+
+    // bool IsInlined();
+    /// Whether this frame is a function inlined into the frame above it, it shares the pc.
+    fn is_inlined(&mut self) -> bool {
+        self.pin_mut().IsInlined()
+    }
+
+    // lldb::SBModule GetModule() const;
+    fn get_module(&self) -> Wrapped<bindings::SBModule> {
+        self.as_ref().GetModule().wrap()
+    }
+
+    // lldb::SBLineEntry GetLineEntry() const;
+    fn get_line_entry(&self) -> Wrapped<bindings::SBLineEntry> {
+        self.as_ref().GetLineEntry().wrap()
+    }
+
+    // lldb::SBValueList GetRegisters();
+    /// The register sets of this frame, with aliases for the architecture of the target.
+    fn registers(&mut self) -> crate::registers::Registers {
+        let triple = self.thread().process().target().get_triple();
+        let sets = self.pin_mut().GetRegisters().wrap().values();
+        crate::registers::Registers::new(
+            crate::calling_convention::Arch::from_triple(&triple),
+            sets,
+        )
+    }
+
+    fn find_register(&mut self, name: &str) -> Wrapped<bindings::SBValue> {
+        let reg = std::ffi::CString::new(name).expect("no null bytes expected");
+        unsafe { self.pin_mut().FindRegister(reg.as_ptr()) }.wrap()
+    }
+
+    // lldb::SBValue EvaluateExpression(const char *expr, const SBExpressionOptions &options);
+    /// Evaluate an expression in the context of this frame, failing with the diagnostics if it
+    /// doesn't compile or run.
+    fn evaluate(
+        &mut self,
+        expr: &str,
+        options: &crate::expression::ExpressionOptions,
+    ) -> Result<Wrapped<bindings::SBValue>, crate::expression::ExpressionError> {
+        let expr = std::ffi::CString::new(expr).expect("no null bytes expected");
+        let sb_options = options.to_sb();
+        let res = unsafe {
+            self.pin_mut()
+                .EvaluateExpression3(expr.as_ptr(), sb_options.as_ref())
+        }
+        .wrap();
+        crate::expression::check_result(res)
+    }
+
+    fn evaluate_expression(&mut self, expr: &str) -> Wrapped<bindings::SBValue> {
+        let reg = std::ffi::CString::new(expr).expect("no null bytes expected");
+        unsafe { self.pin_mut().EvaluateExpression(reg.as_ptr()) }.wrap()
+    }
+
+    /// Write a register of this frame, the value must fit in the register.
+    fn set_register(&mut self, name: &str, value: u64) -> Result<(), crate::value::WriteError> {
+        use crate::value::WriteError;
+        let mut register = self.find_register(name);
+        if !register.pin_mut().IsValid() {
+            return Err(WriteError::RegisterNotAvailable(name.to_string()));
+        }
+        let size = register.byte_size()?;
+        let needed = (64 - value.leading_zeros() as usize).div_ceil(8);
+        if size < 8 && needed > size {
+            return Err(WriteError::SizeMismatch {
+                expected: size,
+                found: needed,
+            });
+        }
+        register.set_from_str(&value.to_string())
+    }
+
+    // lldb::SBValue FindVariable(const char *var_name);
+    /// A local variable or argument visible in this frame.
+    fn find_variable(&mut self, name: &str) -> Option<Wrapped<bindings::SBValue>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        let mut res = unsafe { self.pin_mut().FindVariable(name.as_ptr()) }.wrap();
+        res.pin_mut().IsValid().then_some(res)
+    }
+
+    /// Like find_variable, but with an error if it isn't found or can't be read.
+    fn variable(&mut self, name: &str) -> SBResult<Wrapped<bindings::SBValue>> {
+        let mut res = self.find_variable(name).ok_or_else(|| {
+            Wrapped::<bindings::SBError>::from_message(&format!("no variable named {name}"))
+        })?;
+        res.check()?;
+        Ok(res)
+    }
+
+    // lldb::SBValue FindValue(const char *name, ValueType value_type);
+    /// Find a variable, register or register set of the given kind.
+    fn find_value(
+        &mut self,
+        name: &str,
+        value_type: bindings::ValueType,
+    ) -> Option<Wrapped<bindings::SBValue>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        let mut res = unsafe { self.pin_mut().FindValue(name.as_ptr(), value_type) }.wrap();
+        res.pin_mut().IsValid().then_some(res)
+    }
+
+    // lldb::SBValue GetValueForVariablePath(const char *var_path);
+    /// A variable path like `cfg->items[2].name`, only variables and member access, no expressions.
+    fn get_value_for_variable_path(&mut self, path: &str) -> Option<Wrapped<bindings::SBValue>> {
+        let path = std::ffi::CString::new(path).expect("no null bytes expected");
+        let mut res = unsafe { self.pin_mut().GetValueForVariablePath1(path.as_ptr()) }.wrap();
+        res.pin_mut().IsValid().then_some(res)
+    }
+
+    // lldb::SBValueList GetVariables(const lldb::SBVariablesOptions &options);
+    fn variables(&mut self, options: &VariablesOptions) -> Vec<Wrapped<bindings::SBValue>> {
+        let sb_options = options.to_sb();
+        self.pin_mut()
+            .GetVariables2(sb_options.as_ref())
+            .wrap()
+            .values()
+    }
+
+    /// The arguments of the function of this frame.
+    fn arguments(&mut self) -> Vec<Wrapped<bindings::SBValue>> {
+        self.variables(&VariablesOptions::new().locals(false))
+    }
+}
+impl<T> Frame for T where T: autocxx::PinMut<bindings::SBFrame> {}
+
+/// Which variables `Frame::variables` returns, defaults to the arguments and locals in scope.
+#[derive(Debug, Clone)]
+pub struct VariablesOptions {
+    arguments: bool,
+    locals: bool,
+    statics: bool,
+    in_scope_only: bool,
+    use_dynamic: bindings::DynamicValueType,
+}
+
+impl Default for VariablesOptions {
+    fn default() -> Self {
+        VariablesOptions {
+            arguments: true,
+            locals: true,
+            statics: false,
+            in_scope_only: true,
+            use_dynamic: bindings::DynamicValueType::eDynamicDontRunTarget,
+        }
+    }
+}
+
+impl VariablesOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn arguments(mut self, include: bool) -> Self {
+        self.arguments = include;
+        self
+    }
+
+    pub fn locals(mut self, include: bool) -> Self {
+        self.locals = include;
+        self
+    }
+
+    pub fn statics(mut self, include: bool) -> Self {
+        self.statics = include;
+        self
+    }
+
+    /// Only locals whose scope contains the current pc, not those declared further down.
+    pub fn in_scope_only(mut self, in_scope_only: bool) -> Self {
+        self.in_scope_only = in_scope_only;
+        self
+    }
+
+    /// Whether to present values as their dynamic type.
+    pub fn use_dynamic(mut self, use_dynamic: bindings::DynamicValueType) -> Self {
+        self.use_dynamic = use_dynamic;
+        self
+    }
+
+    fn to_sb(&self) -> Wrapped<bindings::SBVariablesOptions> {
+        let mut res = bindings::SBVariablesOptions::new().wrap();
+        res.pin_mut().SetIncludeArguments(self.arguments);
+        res.pin_mut().SetIncludeLocals(self.locals);
+        res.pin_mut().SetIncludeStatics(self.statics);
+        res.pin_mut().SetInScopeOnly(self.in_scope_only);
+        res.pin_mut().SetUseDynamic(self.use_dynamic.clone());
+        res
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBValueList.h
+handle_box_and_uniqueptr!(bindings::SBValueList);
+pub trait ValueList: autocxx::PinMut<bindings::SBValueList> {
+    // uint32_t GetSize() const;
+    fn get_size(&self) -> u32 {
+        self.as_ref().GetSize()
+    }
+
+    // lldb::SBValue GetValueAtIndex(uint32_t idx) const;
+    fn value_at_index(&self, index: u32) -> Wrapped<bindings::SBValue> {
+        self.as_ref().GetValueAtIndex(index).wrap()
+    }
+
+    fn values(&self) -> Vec<Wrapped<bindings::SBValue>> {
+        (0..self.get_size())
+            .map(|i| self.value_at_index(i))
+            .collect()
+    }
+}
+impl<T> ValueList for T where T: autocxx::PinMut<bindings::SBValueList> {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BreakpointId(pub i32);
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBBreakpoint.h
+handle_box_and_uniqueptr!(bindings::SBBreakpoint);
+pub trait Breakpoint: autocxx::PinMut<bindings::SBBreakpoint> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    fn get_id(&self) -> BreakpointId {
+        BreakpointId(self.as_ref().GetID())
+    }
+
+    fn set_enabled(&mut self, state: bool) {
+        self.pin_mut().SetEnabled(state);
+    }
+
+    fn is_enabled(&mut self) -> bool {
+        self.pin_mut().IsEnabled()
+    }
+
+    /// A one shot breakpoint deletes itself after it has been hit.
+    fn set_one_shot(&mut self, state: bool) {
+        self.pin_mut().SetOneShot(state);
+    }
+
+    fn is_one_shot(&self) -> bool {
+        self.as_ref().IsOneShot()
+    }
+
+    /// Only stop for this thread.
+    fn set_thread_id(&mut self, thread_id: u64) {
+        self.pin_mut().SetThreadID(thread_id);
+    }
+
+    fn get_hit_count(&self) -> u32 {
+        self.as_ref().GetHitCount()
+    }
+
+    fn get_num_locations(&self) -> usize {
+        self.as_ref().GetNumLocations()
+    }
+}
+impl<T> Breakpoint for T where T: autocxx::PinMut<bindings::SBBreakpoint> {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WatchpointId(pub i32);
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBWatchpoint.h
+handle_box_and_uniqueptr!(bindings::SBWatchpoint);
+pub trait Watchpoint: autocxx::PinMut<bindings::SBWatchpoint> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    fn set_enabled(&mut self, state: bool) {
+        self.pin_mut().SetEnabled(state);
+    }
+
+    fn is_enabled(&mut self) -> bool {
+        self.pin_mut().IsEnabled()
+    }
+
+    fn get_id(&mut self) -> WatchpointId {
+        WatchpointId(self.pin_mut().GetID())
+    }
+
+    /// Number of times this watchpoint has been hit.
+    fn get_hit_count(&mut self) -> u32 {
+        self.pin_mut().GetHitCount()
+    }
+
+    /// Number of hits that will be ignored before the watchpoint stops the process.
+    fn get_ignore_count(&mut self) -> u32 {
+        self.pin_mut().GetIgnoreCount()
+    }
+
+    fn set_ignore_count(&mut self, count: u32) {
+        self.pin_mut().SetIgnoreCount(count);
+    }
+
+    /// Returns the condition expression, None if there is no condition.
+    fn get_condition(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetCondition())
+    }
+
+    /// Set the condition expression, an empty string removes the condition.
+    fn set_condition(&mut self, condition: &str) {
+        let cond = std::ffi::CString::new(condition).expect("no null bytes expected");
+        unsafe { self.pin_mut().SetCondition(cond.as_ptr()) };
+    }
+
+    /// The address this watchpoint is watching.
+    fn get_watch_address(&mut self) -> Addr {
+        self.pin_mut().GetWatchAddress()
+    }
+
+    /// The number of bytes this watchpoint is watching.
+    fn get_watch_size(&mut self) -> usize {
+        self.pin_mut().GetWatchSize()
+    }
+}
+impl<T> Watchpoint for T where T: autocxx::PinMut<bindings::SBWatchpoint> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBInstructionList.h
+handle_box_and_uniqueptr!(bindings::SBInstructionList);
+pub trait InstructionList: autocxx::PinMut<bindings::SBInstructionList> {
+    fn get_size(&mut self) -> usize {
+        self.pin_mut().GetSize()
+    }
+
+    fn instruction(&mut self, index: u32) -> Wrapped<bindings::SBInstruction> {
+        self.pin_mut().GetInstructionAtIndex(index).wrap()
+    }
+}
+impl<T> InstructionList for T where T: autocxx::PinMut<bindings::SBInstructionList> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBInstruction.h
+handle_box_and_uniqueptr!(bindings::SBInstruction);
+pub trait Instruction: autocxx::PinMut<bindings::SBInstruction> {
+    fn does_branch(&mut self) -> bool {
+        self.pin_mut().DoesBranch()
+    }
+
+    fn get_byte_size(&mut self) -> usize {
+        self.pin_mut().GetByteSize()
+    }
+
+    /// The load address of this instruction in the provided target.
+    fn get_load_address<T: Target>(&mut self, target: &T) -> Addr {
+        let address = self.pin_mut().GetAddress().within_box();
+        address.GetLoadAddress(target.as_ref())
+    }
+}
+impl<T> Instruction for T where T: autocxx::PinMut<bindings::SBInstruction> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBFileSpec.h
+handle_box_and_uniqueptr!(bindings::SBFileSpec);
+pub trait FileSpec: autocxx::PinMut<bindings::SBFileSpec> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // uint32_t GetPath(char *dst_path, size_t dst_len) const;
+    /// The full path of this file.
+    fn get_path(&self) -> String {
+        let mut res = vec![0u8; 4096];
+        let len = unsafe { self.as_ref().GetPath(res.as_mut_ptr() as _, res.len()) } as usize;
+        res.truncate(len.min(res.len()));
+        String::from_utf8_lossy(&res).into_owned()
+    }
+
+    // const char *GetFilename() const;
+    /// The last component of the path, like `kernel32.dll`.
+    fn get_filename(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetFilename())
+    }
+}
+impl<T> FileSpec for T where T: autocxx::PinMut<bindings::SBFileSpec> {}
+
+impl Wrapped<bindings::SBFileSpec> {
+    /// A file spec for this path, without resolving it. A bare file name matches modules with that
+    /// name in any directory.
+    pub fn from_path(path: &str) -> Self {
+        let path = std::path::Path::new(path);
+        let mut res = bindings::SBFileSpec::new().wrap();
+        if let Some(name) = path.file_name() {
+            let name = std::ffi::CString::new(name.to_string_lossy().as_bytes())
+                .expect("no null bytes expected");
+            unsafe { res.pin_mut().SetFilename(name.as_ptr()) };
+        }
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            let dir = std::ffi::CString::new(dir.to_string_lossy().as_bytes())
+                .expect("no null bytes expected");
+            unsafe { res.pin_mut().SetDirectory(dir.as_ptr()) };
+        }
+        res
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBModule.h
+handle_box_and_uniqueptr!(bindings::SBModule);
+pub trait Module: autocxx::PinMut<bindings::SBModule> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBFileSpec GetFileSpec() const;
+    fn get_file_spec(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetFileSpec().wrap()
+    }
+
+    // lldb::SBFileSpec GetPlatformFileSpec() const;
+    /// The path of the module on the system being debugged, which may differ from the local file
+    /// when debugging remotely.
+    fn get_platform_file_spec(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetPlatformFileSpec().wrap()
+    }
+
+    // const char *GetUUIDString() const;
+    /// The UUID or build id of the module, None if the object file doesn't have one.
+    fn get_uuid_string(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetUUIDString())
+    }
+
+    // const char *GetTriple();
+    fn get_triple(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetTriple())
+    }
+
+    // lldb::SBAddress GetObjectFileHeaderAddress() const;
+    /// The address of the object file header, for PE files this is the image base.
+    fn get_object_file_header_address(&self) -> Wrapped<bindings::SBAddress> {
+        self.as_ref().GetObjectFileHeaderAddress().wrap()
+    }
+
+    /// The address the module got loaded at in the target, None if it isn't loaded.
+    fn load_base<T: Target>(&self, target: &T) -> Option<Addr> {
+        self.get_object_file_header_address().load_address(target)
+    }
+
+    // size_t GetNumSections();
+    fn get_num_sections(&mut self) -> usize {
+        self.pin_mut().GetNumSections()
+    }
+
+    // lldb::SBSection GetSectionAtIndex(size_t idx);
+    fn section_at_index(&mut self, index: usize) -> Wrapped<bindings::SBSection> {
+        self.pin_mut().GetSectionAtIndex(index).wrap()
+    }
+
+    /// The top level sections of the module.
+    fn sections(&mut self) -> Vec<Wrapped<bindings::SBSection>> {
+        (0..self.get_num_sections())
+            .map(|i| self.section_at_index(i))
+            .collect()
+    }
+
+    // lldb::SBSection FindSection(const char *sect_name);
+    /// Find a top level section by name, like `.text`.
+    fn find_section(&mut self, name: &str) -> Option<Wrapped<bindings::SBSection>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        let res = unsafe { self.pin_mut().FindSection(name.as_ptr()) }.wrap();
+        if res.is_valid() {
+            return Some(res);
+        }
+        None
+    }
+
+    // size_t GetNumSymbols();
+    fn get_num_symbols(&mut self) -> usize {
+        self.pin_mut().GetNumSymbols()
+    }
+
+    // uint32_t GetNumCompileUnits();
+    fn get_num_compile_units(&mut self) -> u32 {
+        self.pin_mut().GetNumCompileUnits()
+    }
+
+    // lldb::SBCompileUnit GetCompileUnitAtIndex(uint32_t);
+    fn compile_unit_at_index(&mut self, index: u32) -> Wrapped<bindings::SBCompileUnit> {
+        self.pin_mut().GetCompileUnitAtIndex(index).wrap()
+    }
+
+    /// The compile units with debug info in this module.
+    fn compile_units(&mut self) -> Vec<Wrapped<bindings::SBCompileUnit>> {
+        (0..self.get_num_compile_units())
+            .map(|i| self.compile_unit_at_index(i))
+            .collect()
+    }
+
+    // lldb::SBSymbol FindSymbol(const char *name, lldb::SymbolType type = eSymbolTypeAny);
+    fn find_symbol(
+        &mut self,
+        name: &str,
+        symbol_type: bindings::SymbolType,
+    ) -> Option<Wrapped<bindings::SBSymbol>> {
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        let res = unsafe { self.pin_mut().FindSymbol(name.as_ptr(), symbol_type) }.wrap();
+        if res.is_valid() {
+            return Some(res);
+        }
+        None
+    }
+
+    // lldb::SBAddress ResolveFileAddress(lldb::addr_t vm_addr);
+    /// The section relative address for a virtual address in the file image.
+    fn resolve_file_address(&mut self, file_address: Addr) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().ResolveFileAddress(file_address).wrap()
+    }
+}
+impl<T> Module for T where T: autocxx::PinMut<bindings::SBModule> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBModuleSpec.h
+handle_box_and_uniqueptr!(bindings::SBModuleSpec);
+pub trait ModuleSpec: autocxx::PinMut<bindings::SBModuleSpec> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBFileSpec GetFileSpec();
+    fn get_file_spec(&mut self) -> Wrapped<bindings::SBFileSpec> {
+        self.pin_mut().GetFileSpec().wrap()
+    }
+
+    // void SetFileSpec(const lldb::SBFileSpec &fspec);
+    fn set_file_spec<F: FileSpec>(&mut self, file: &F) {
+        self.pin_mut().SetFileSpec(file.as_ref())
+    }
+
+    // const char *GetTriple();
+    fn get_triple(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetTriple())
+    }
+
+    // void SetTriple(const char *triple);
+    fn set_triple(&mut self, triple: &str) {
+        let triple = std::ffi::CString::new(triple).expect("no null bytes expected");
+        unsafe { self.pin_mut().SetTriple(triple.as_ptr()) }
+    }
+}
+impl<T> ModuleSpec for T where T: autocxx::PinMut<bindings::SBModuleSpec> {}
+
+impl Wrapped<bindings::SBModuleSpec> {
+    /// A spec for the module at this path, for use with [`Target::add_module_spec`].
+    pub fn from_path(path: &str) -> Self {
+        let mut res = bindings::SBModuleSpec::new().wrap();
+        res.set_file_spec(&Wrapped::<bindings::SBFileSpec>::from_path(path));
+        res
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBModuleSpec.h
+handle_box_and_uniqueptr!(bindings::SBModuleSpecList);
+pub trait ModuleSpecList: autocxx::PinMut<bindings::SBModuleSpecList> {
+    // size_t GetSize();
+    fn get_size(&mut self) -> usize {
+        self.pin_mut().GetSize()
+    }
+
+    // lldb::SBModuleSpec GetSpecAtIndex(size_t i);
+    fn spec_at_index(&mut self, index: usize) -> Wrapped<bindings::SBModuleSpec> {
+        self.pin_mut().GetSpecAtIndex(index).wrap()
+    }
+
+    fn specs(&mut self) -> Vec<Wrapped<bindings::SBModuleSpec>> {
+        (0..self.get_size())
+            .map(|i| self.spec_at_index(i))
+            .collect()
+    }
+}
+impl<T> ModuleSpecList for T where T: autocxx::PinMut<bindings::SBModuleSpecList> {}
+
+impl Wrapped<bindings::SBModuleSpecList> {
+    // static SBModuleSpecList GetModuleSpecifications(const char *path);
+    /// The specs of the object file at this path, one per architecture for universal binaries.
+    pub fn for_path(path: &str) -> Self {
+        let path = std::ffi::CString::new(path).expect("no null bytes expected");
+        unsafe { bindings::SBModuleSpecList::GetModuleSpecifications(path.as_ptr()) }.wrap()
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBSection.h
+handle_box_and_uniqueptr!(bindings::SBSection);
+pub trait Section: autocxx::PinMut<bindings::SBSection> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // const char *GetName();
+    fn get_name(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetName())
+    }
+
+    // lldb::addr_t GetFileAddress();
+    fn get_file_address(&mut self) -> Addr {
+        self.pin_mut().GetFileAddress()
+    }
+
+    // lldb::addr_t GetLoadAddress(lldb::SBTarget &target);
+    /// Where the section got loaded in the target, None if it isn't loaded.
+    fn get_load_address<T: Target>(&mut self, target: &mut T) -> Option<Addr> {
+        let res = self.pin_mut().GetLoadAddress(target.pin_mut());
+        (res != u64::MAX).then_some(res)
+    }
+
+    // lldb::addr_t GetByteSize();
+    fn get_byte_size(&mut self) -> u64 {
+        self.pin_mut().GetByteSize()
+    }
+
+    // uint64_t GetFileOffset();
+    fn get_file_offset(&mut self) -> u64 {
+        self.pin_mut().GetFileOffset()
+    }
+
+    // size_t GetNumSubSections();
+    fn get_num_sub_sections(&mut self) -> usize {
+        self.pin_mut().GetNumSubSections()
+    }
+
+    // lldb::SBSection GetSubSectionAtIndex(size_t idx);
+    fn sub_section_at_index(&mut self, index: usize) -> Wrapped<bindings::SBSection> {
+        self.pin_mut().GetSubSectionAtIndex(index).wrap()
+    }
+}
+impl<T> Section for T where T: autocxx::PinMut<bindings::SBSection> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBAddress.h
+handle_box_and_uniqueptr!(bindings::SBAddress);
+pub trait Address: autocxx::PinMut<bindings::SBAddress> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // addr_t GetFileAddress() const;
+    fn get_file_address(&self) -> Option<Addr> {
+        let res = self.as_ref().GetFileAddress();
+        (res != u64::MAX).then_some(res)
+    }
+
+    // addr_t GetLoadAddress(const lldb::SBTarget &target) const;
+    /// The address in the running process, None if the containing module isn't loaded.
+    fn load_address<T: Target>(&self, target: &T) -> Option<Addr> {
+        let res = self.as_ref().GetLoadAddress(target.as_ref());
+        (res != u64::MAX).then_some(res)
+    }
+
+    // lldb::SBSection GetSection();
+    fn get_section(&mut self) -> Wrapped<bindings::SBSection> {
+        self.pin_mut().GetSection().wrap()
+    }
+
+    // lldb::addr_t GetOffset();
+    /// The offset into the section, or the absolute address if there is no section.
+    fn get_offset(&mut self) -> Addr {
+        self.pin_mut().GetOffset()
+    }
+
+    // lldb::SBModule GetModule();
+    fn get_module(&mut self) -> Wrapped<bindings::SBModule> {
+        self.pin_mut().GetModule().wrap()
+    }
+
+    // lldb::SBSymbol GetSymbol();
+    fn get_symbol(&mut self) -> Wrapped<bindings::SBSymbol> {
+        self.pin_mut().GetSymbol().wrap()
+    }
+
+    // lldb::SBFunction GetFunction();
+    fn get_function(&mut self) -> Wrapped<bindings::SBFunction> {
+        self.pin_mut().GetFunction().wrap()
+    }
+}
+impl<T> Address for T where T: autocxx::PinMut<bindings::SBAddress> {}
+
+impl Wrapped<bindings::SBAddress> {
+    /// An address at an offset into a module. The offset is relative to the section if one is
+    /// given, otherwise to the start of the image, like the `module.dll+0x1d50` notation. The
+    /// address stays relative to the module, such that it resolves wherever the module is loaded.
+    pub fn from_module_offset<M: Module>(
+        module: &mut M,
+        section: Option<&str>,
+        offset: Addr,
+    ) -> SBResult<Self> {
+        let res = match section {
+            Some(name) => {
+                let section = module.find_section(name).ok_or_else(|| {
+                    Wrapped::<bindings::SBError>::from_message(&format!("no section {name}"))
+                })?;
+                let mut res = bindings::SBAddress::new().wrap();
+                res.pin_mut().SetAddress(section.as_ref(), offset);
+                res
+            }
+            None => {
+                let base = module
+                    .get_object_file_header_address()
+                    .get_file_address()
+                    .ok_or_else(|| {
+                        Wrapped::<bindings::SBError>::from_message("module has no image base")
+                    })?;
+                module.resolve_file_address(base + offset)
+            }
+        };
+        if !res.is_valid() {
+            return Err(Wrapped::<bindings::SBError>::from_message(&format!(
+                "offset 0x{offset:x} is outside of the module"
+            )));
+        }
+        Ok(res)
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBSymbol.h
+handle_box_and_uniqueptr!(bindings::SBSymbol);
+pub trait Symbol: autocxx::PinMut<bindings::SBSymbol> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // const char *GetName() const;
+    fn get_name(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetName())
+    }
+
+    // const char *GetDisplayName() const;
+    /// The demangled name, without arguments for C++ functions.
+    fn get_display_name(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetDisplayName())
+    }
+
+    // const char *GetMangledName();
+    fn get_mangled_name(&mut self) -> Option<String> {
+        to_string_opt(self.pin_mut().GetMangledName())
+    }
+
+    // SBAddress GetStartAddress();
+    fn get_start_address(&mut self) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().GetStartAddress().wrap()
+    }
+
+    // SBAddress GetEndAddress();
+    fn get_end_address(&mut self) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().GetEndAddress().wrap()
+    }
+
+    // lldb::SymbolType GetType();
+    fn get_symbol_type(&mut self) -> bindings::SymbolType {
+        self.pin_mut().GetType()
+    }
+}
+impl<T> Symbol for T where T: autocxx::PinMut<bindings::SBSymbol> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBFunction.h
+handle_box_and_uniqueptr!(bindings::SBFunction);
+pub trait Function: autocxx::PinMut<bindings::SBFunction> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // const char *GetName() const;
+    fn get_name(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetName())
+    }
+
+    // const char *GetDisplayName() const;
+    fn get_display_name(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetDisplayName())
+    }
+
+    // lldb::SBAddress GetStartAddress();
+    fn get_start_address(&mut self) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().GetStartAddress().wrap()
+    }
+
+    // lldb::SBAddress GetEndAddress();
+    fn get_end_address(&mut self) -> Wrapped<bindings::SBAddress> {
+        self.pin_mut().GetEndAddress().wrap()
+    }
+}
+impl<T> Function for T where T: autocxx::PinMut<bindings::SBFunction> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBSymbolContext.h
+handle_box_and_uniqueptr!(bindings::SBSymbolContext);
+pub trait SymbolContext: autocxx::PinMut<bindings::SBSymbolContext> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBModule GetModule();
+    fn get_module(&mut self) -> Wrapped<bindings::SBModule> {
+        self.pin_mut().GetModule().wrap()
+    }
+
+    // lldb::SBFunction GetFunction();
+    /// The function with debug info, invalid if there is no debug info for this location.
+    fn get_function(&mut self) -> Wrapped<bindings::SBFunction> {
+        self.pin_mut().GetFunction().wrap()
+    }
+
+    // lldb::SBSymbol GetSymbol();
+    fn get_symbol(&mut self) -> Wrapped<bindings::SBSymbol> {
+        self.pin_mut().GetSymbol().wrap()
+    }
+
+    // lldb::SBCompileUnit GetCompileUnit();
+    fn get_compile_unit(&mut self) -> Wrapped<bindings::SBCompileUnit> {
+        self.pin_mut().GetCompileUnit().wrap()
+    }
+
+    // lldb::SBLineEntry GetLineEntry();
+    fn get_line_entry(&mut self) -> Wrapped<bindings::SBLineEntry> {
+        self.pin_mut().GetLineEntry().wrap()
+    }
+
+    // lldb::SBBlock GetBlock();
+    /// The innermost lexical block, inlined functions are blocks too.
+    fn get_block(&mut self) -> Wrapped<bindings::SBBlock> {
+        self.pin_mut().GetBlock().wrap()
+    }
+
+    /// The start of the function, or of the symbol if there is no debug info.
+    fn start_address(&mut self) -> Option<Wrapped<bindings::SBAddress>> {
+        let mut function = self.get_function();
+        if function.is_valid() {
+            return Some(function.get_start_address());
+        }
+        let mut symbol = self.get_symbol();
+        if symbol.is_valid() {
+            return Some(symbol.get_start_address());
+        }
+        None
+    }
+}
+impl<T> SymbolContext for T where T: autocxx::PinMut<bindings::SBSymbolContext> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBLineEntry.h
+handle_box_and_uniqueptr!(bindings::SBLineEntry);
+pub trait LineEntry: autocxx::PinMut<bindings::SBLineEntry> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBAddress GetStartAddress() const;
+    fn get_start_address(&self) -> Wrapped<bindings::SBAddress> {
+        self.as_ref().GetStartAddress().wrap()
+    }
+
+    // lldb::SBAddress GetEndAddress() const;
+    /// The address just past the code of this line entry.
+    fn get_end_address(&self) -> Wrapped<bindings::SBAddress> {
+        self.as_ref().GetEndAddress().wrap()
+    }
+
+    // lldb::SBFileSpec GetFileSpec() const;
+    fn get_file_spec(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetFileSpec().wrap()
+    }
+
+    // uint32_t GetLine() const;
+    fn get_line(&self) -> u32 {
+        self.as_ref().GetLine()
+    }
+
+    // uint32_t GetColumn() const;
+    /// The column, zero if not known.
+    fn get_column(&self) -> u32 {
+        self.as_ref().GetColumn()
+    }
+}
+impl<T> LineEntry for T where T: autocxx::PinMut<bindings::SBLineEntry> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBBlock.h
+handle_box_and_uniqueptr!(bindings::SBBlock);
+pub trait Block: autocxx::PinMut<bindings::SBBlock> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // bool IsInlined() const;
+    fn is_inlined(&self) -> bool {
+        self.as_ref().IsInlined()
+    }
+
+    // const char *GetInlinedName() const;
+    /// Name of the inlined function, None if this block isn't inlined.
+    fn get_inlined_name(&self) -> Option<String> {
+        to_string_opt(self.as_ref().GetInlinedName())
+    }
+
+    // lldb::SBFileSpec GetInlinedCallSiteFile() const;
+    /// The file the function was inlined into.
+    fn get_inlined_call_site_file(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetInlinedCallSiteFile().wrap()
+    }
+
+    // uint32_t GetInlinedCallSiteLine() const;
+    fn get_inlined_call_site_line(&self) -> u32 {
+        self.as_ref().GetInlinedCallSiteLine()
+    }
+
+    // uint32_t GetInlinedCallSiteColumn() const;
+    fn get_inlined_call_site_column(&self) -> u32 {
+        self.as_ref().GetInlinedCallSiteColumn()
+    }
+
+    // lldb::SBBlock GetParent();
+    fn get_parent(&mut self) -> Wrapped<bindings::SBBlock> {
+        self.pin_mut().GetParent().wrap()
+    }
+
+    // lldb::SBBlock GetContainingInlinedBlock();
+    /// The closest inlined block containing this one, this block itself if it is inlined.
+    fn get_containing_inlined_block(&mut self) -> Wrapped<bindings::SBBlock> {
+        self.pin_mut().GetContainingInlinedBlock().wrap()
+    }
+}
+impl<T> Block for T where T: autocxx::PinMut<bindings::SBBlock> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBCompileUnit.h
+handle_box_and_uniqueptr!(bindings::SBCompileUnit);
+pub trait CompileUnit: autocxx::PinMut<bindings::SBCompileUnit> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBFileSpec GetFileSpec() const;
+    /// The main source file of the compile unit.
+    fn get_file_spec(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetFileSpec().wrap()
+    }
+
+    // lldb::LanguageType GetLanguage();
+    fn get_language(&mut self) -> bindings::LanguageType {
+        self.pin_mut().GetLanguage()
+    }
+
+    // uint32_t GetNumLineEntries() const;
+    fn get_num_line_entries(&self) -> u32 {
+        self.as_ref().GetNumLineEntries()
+    }
+
+    // lldb::SBLineEntry GetLineEntryAtIndex(uint32_t idx) const;
+    fn line_entry_at_index(&self, index: u32) -> Wrapped<bindings::SBLineEntry> {
+        self.as_ref().GetLineEntryAtIndex(index).wrap()
+    }
+
+    /// The line table, in address order. Code from headers shows up with the file of the header.
+    fn line_entries(&self) -> Vec<Wrapped<bindings::SBLineEntry>> {
+        (0..self.get_num_line_entries())
+            .map(|i| self.line_entry_at_index(i))
+            .collect()
+    }
+
+    // uint32_t FindLineEntryIndex(uint32_t start_idx, uint32_t line, lldb::SBFileSpec *inline_file_spec, bool exact) const;
+    /// Index of the next line entry from `start` for the line in the file, which can also be a
+    /// header used by the compile unit. Without `exact` the nearest following line is accepted.
+    fn find_line_entry_index<F: FileSpec>(
+        &self,
+        start: u32,
+        line: u32,
+        file: &mut F,
+        exact: bool,
+    ) -> Option<u32> {
+        let res = unsafe {
+            let file: *mut bindings::SBFileSpec = file.pin_mut().get_unchecked_mut();
+            self.as_ref().FindLineEntryIndex1(start, line, file, exact)
+        };
+        (res != u32::MAX).then_some(res)
+    }
+}
+impl<T> CompileUnit for T where T: autocxx::PinMut<bindings::SBCompileUnit> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBDeclaration.h
+handle_box_and_uniqueptr!(bindings::SBDeclaration);
+pub trait Declaration: autocxx::PinMut<bindings::SBDeclaration> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // lldb::SBFileSpec GetFileSpec() const;
+    fn get_file_spec(&self) -> Wrapped<bindings::SBFileSpec> {
+        self.as_ref().GetFileSpec().wrap()
+    }
+
+    // uint32_t GetLine() const;
+    fn get_line(&self) -> u32 {
+        self.as_ref().GetLine()
+    }
+
+    // uint32_t GetColumn() const;
+    /// The column, zero if not known.
+    fn get_column(&self) -> u32 {
+        self.as_ref().GetColumn()
+    }
+}
+impl<T> Declaration for T where T: autocxx::PinMut<bindings::SBDeclaration> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBSourceManager.h
+handle_box_and_uniqueptr!(bindings::SBSourceManager);
+pub trait SourceManager: autocxx::PinMut<bindings::SBSourceManager> {
+    // size_t DisplaySourceLinesWithLineNumbers(const lldb::SBFileSpec &file, uint32_t line, uint32_t context_before, uint32_t context_after, const char *current_line_cstr, lldb::SBStream &s);
+    /// The source lines around `line` with line numbers, `line` itself is marked with `->` like
+    /// LLDB shows the source at a stop. Empty if the file can't be read.
+    fn display_lines<F: FileSpec>(&mut self, file: &F, line: u32, context: u32) -> String {
+        let mut stream = bindings::SBStream::new().within_unique_ptr();
+        let marker = std::ffi::CString::new("->").expect("no null bytes expected");
+        unsafe {
+            self.pin_mut().DisplaySourceLinesWithLineNumbers(
+                file.as_ref(),
+                line,
+                context,
+                context,
+                marker.as_ptr(),
+                stream.pin_mut(),
+            )
+        };
+        to_string_opt(stream.pin_mut().GetData()).unwrap_or_default()
+    }
+}
+impl<T> SourceManager for T where T: autocxx::PinMut<bindings::SBSourceManager> {}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBSymbolContextList.h
+handle_box_and_uniqueptr!(bindings::SBSymbolContextList);
+pub trait SymbolContextList: autocxx::PinMut<bindings::SBSymbolContextList> {
+    // uint32_t GetSize() const;
+    fn get_size(&self) -> u32 {
+        self.as_ref().GetSize()
+    }
+
+    // lldb::SBSymbolContext GetContextAtIndex(uint32_t idx);
+    fn context_at_index(&mut self, index: u32) -> Wrapped<bindings::SBSymbolContext> {
+        self.pin_mut().GetContextAtIndex(index).wrap()
+    }
+
+    fn contexts(&mut self) -> Vec<Wrapped<bindings::SBSymbolContext>> {
+        (0..self.get_size())
+            .map(|i| self.context_at_index(i))
+            .collect()
+    }
+}
+impl<T> SymbolContextList for T where T: autocxx::PinMut<bindings::SBSymbolContextList> {}
+
+// Not a single method is const on SBValue, which makes it tricky to say... debug print, which
+// is a pretty big problem.
+handle_box_and_uniqueptr!(bindings::SBValue);
+pub trait Value: autocxx::PinMut<bindings::SBValue> {
+    fn get_value_unsigned(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
+        let mut e = bindings::SBError::new().wrap();
+        let res = self.pin_mut().GetValueAsUnsigned(e.pin_mut(), 0);
+        if e.is_success() {
+            return Ok(res);
+        }
+        Err(e)
+    }
+    fn get_value_u64(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
+        self.get_value_unsigned()
+    }
+
+    // For uniformity with GetValueAsUnsigned
+    fn get_value_as_unsigned(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
+        self.get_value_unsigned()
+    }
+
+    // int64_t GetValueAsSigned(lldb::SBError &error, int64_t fail_value = 0);
+    fn get_value_signed(&mut self) -> SBResult<i64> {
         let mut e = bindings::SBError::new().wrap();
-        let ret = unsafe {
-            self.pin_mut()
-                .ReadCStringFromMemory(address, res.as_mut_ptr() as _, size, e.pin_mut())
-        };
+        let res = self.pin_mut().GetValueAsSigned(e.pin_mut(), 0);
         if e.is_success() {
-            let relevant = &res[0..ret];
-            let res = std::ffi::CString::new(relevant).expect("found null byte in string");
             return Ok(res);
         }
         Err(e)
     }
 
-    // uint32_t GetNumSupportedHardwareWatchpoints(lldb::SBError &error) const;
-    fn get_num_supported_hardware_watchpoints(&mut self) -> SBResult<u32> {
+    fn get_value_usize(&mut self) -> Result<usize, Wrapped<bindings::SBError>> {
         let mut e = bindings::SBError::new().wrap();
-        let ret = self
-            .pin_mut()
-            .GetNumSupportedHardwareWatchpoints(e.pin_mut());
+        let res = self.pin_mut().GetValueAsUnsigned(e.pin_mut(), 0) as usize;
         if e.is_success() {
-            return Ok(ret);
+            return Ok(res);
         }
         Err(e)
     }
-}
-// This works:
-impl<T: autocxx::PinMut<bindings::SBProcess>> Process for T {}
-// But so does this:
-// impl<T> Process for T where T: autocxx::PinMut<bindings::SBProcess> {}
-// And these three:
-// impl Process for UniquePtr<bindings::SBProcess>  {}
-// impl Process for Wrapped<bindings::SBProcess>  {}
-// impl Process for Pin<Box<bindings::SBProcess>>  {}
-// Not sure which one is superior atm.
 
-// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBTarget.h
-handle_box_and_uniqueptr!(bindings::SBTarget);
-pub trait Target: autocxx::PinMut<bindings::SBTarget> {
-    // lldb::SBWatchpoint WatchAddress(lldb::addr_t addr, size_t size, bool read, bool write, SBError &error);
-    /// Set a watch point at the specified location and size, check
-    /// [`Process::get_num_supported_hardware_watchpoints`] to confirm there's hardware watchpoints.
-    /// Size cannot be an arbitrary value either, for example 96 fails, possibly only register
-    /// widths?
-    /// I ran into 'sending gdb watchpoint failed' errors, which may have been caused by trying to
-    /// set a 8 byte wide watchpoint on a 32 bits process, using 4 byte width worked.
-    fn watch_address(
+    // lldb::SBWatchpoint Watch(bool resolve_location, bool read, bool write, SBError &error);
+    /// Set a watchpoint on the memory backing this value, if `resolve` is true the location is
+    /// resolved to a load address first. The same hardware limitations as for
+    /// [`Target::watch_address`] apply.
+    fn watch(
         &mut self,
-        address: u64,
-        size: usize,
+        resolve: bool,
         read: bool,
         write: bool,
     ) -> SBResult<Wrapped<bindings::SBWatchpoint>> {
         let mut e = bindings::SBError::new().wrap();
         let res = self
             .pin_mut()
-            .WatchAddress(address, size, read, write, e.pin_mut())
+            .Watch(resolve, read, write, e.pin_mut())
             .wrap();
         if e.is_success() {
             return Ok(res);
@@ -219,79 +2166,266 @@ pub trait Target: autocxx::PinMut<bindings::SBTarget> {
         Err(e)
     }
 
-    // fn delete_watchpoint(&mut self,
-    fn delete_watchpoint(&mut self, watchpoint_id: WatchpointId) -> bool {
-        self.pin_mut().DeleteWatchpoint(watchpoint_id.0)
+    // lldb::SBError GetError();
+    /// Ok if the value is valid and evaluating it didn't result in an error.
+    fn check(&mut self) -> SBResult<()> {
+        if !self.pin_mut().IsValid() {
+            return Err(Wrapped::<bindings::SBError>::from_message("invalid value"));
+        }
+        let e = self.pin_mut().GetError().wrap();
+        if e.is_fail() {
+            return Err(e);
+        }
+        Ok(())
     }
-}
-impl<T> Target for T where T: autocxx::PinMut<bindings::SBTarget> {}
 
-// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBThread.h
-handle_box_and_uniqueptr!(bindings::SBThread);
-pub trait Thread: autocxx::PinMut<bindings::SBThread> {
-    fn frame(&mut self, id: u32) -> Wrapped<bindings::SBFrame> {
-        self.pin_mut().GetFrameAtIndex(id).wrap()
+    // const char *GetName();
+    fn get_name(&mut self) -> SBResult<String> {
+        self.check()?;
+        Ok(to_string_opt(self.pin_mut().GetName()).unwrap_or_default())
     }
-}
-impl<T> Thread for T where T: autocxx::PinMut<bindings::SBThread> {}
 
-// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBFrame.h
-handle_box_and_uniqueptr!(bindings::SBFrame);
-pub trait Frame: autocxx::PinMut<bindings::SBFrame> {
-    fn find_register(&mut self, name: &str) -> Wrapped<bindings::SBValue> {
-        let reg = std::ffi::CString::new(name).expect("no null bytes expected");
-        unsafe { self.pin_mut().FindRegister(reg.as_ptr()) }.wrap()
+    // const char *GetTypeName();
+    fn type_name(&mut self) -> SBResult<String> {
+        self.check()?;
+        Ok(to_string_opt(self.pin_mut().GetTypeName()).unwrap_or_default())
     }
 
-    fn evaluate_expression(&mut self, expr: &str) -> Wrapped<bindings::SBValue> {
-        let reg = std::ffi::CString::new(expr).expect("no null bytes expected");
-        unsafe { self.pin_mut().EvaluateExpression(reg.as_ptr()) }.wrap()
+    // lldb::SBType GetType();
+    fn get_type(&mut self) -> SBResult<Wrapped<bindings::SBType>> {
+        self.check()?;
+        Ok(self.pin_mut().GetType().wrap())
     }
-}
-impl<T> Frame for T where T: autocxx::PinMut<bindings::SBFrame> {}
 
-pub struct WatchpointId(i32);
-// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBWatchpoint.h
-handle_box_and_uniqueptr!(bindings::SBWatchpoint);
-pub trait Watchpoint: autocxx::PinMut<bindings::SBWatchpoint> {
-    fn set_enabled(&mut self, state: bool) {
-        self.pin_mut().SetEnabled(state);
+    // const char *GetSummary();
+    /// The summary as shown by LLDB, like the string a `char*` points to.
+    fn summary(&mut self) -> SBResult<Option<String>> {
+        self.check()?;
+        Ok(to_string_opt(self.pin_mut().GetSummary()))
     }
 
-    fn get_id(&mut self) -> WatchpointId {
-        WatchpointId(self.pin_mut().GetID())
+    // const char *GetLocation();
+    /// Where the value lives, an address or a register name.
+    fn location(&mut self) -> SBResult<Option<String>> {
+        self.check()?;
+        Ok(to_string_opt(self.pin_mut().GetLocation()))
     }
-}
-impl<T> Watchpoint for T where T: autocxx::PinMut<bindings::SBWatchpoint> {}
 
-// Not a single method is const on SBValue, which makes it tricky to say... debug print, which
-// is a pretty big problem.
-handle_box_and_uniqueptr!(bindings::SBValue);
-pub trait Value: autocxx::PinMut<bindings::SBValue> {
-    fn get_value_unsigned(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
-        let mut e = bindings::SBError::new().wrap();
-        let res = self.pin_mut().GetValueAsUnsigned(e.pin_mut(), 0);
-        if e.is_success() {
-            return Ok(res);
+    // lldb::addr_t GetLoadAddress();
+    fn load_address(&mut self) -> SBResult<Addr> {
+        self.check()?;
+        let res = self.pin_mut().GetLoadAddress();
+        if res == u64::MAX {
+            return Err(Wrapped::<bindings::SBError>::from_message(
+                "value has no load address",
+            ));
         }
-        Err(e)
+        Ok(res)
     }
-    fn get_value_u64(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
-        self.get_value_unsigned()
+
+    // size_t GetByteSize();
+    fn byte_size(&mut self) -> SBResult<usize> {
+        self.check()?;
+        Ok(self.pin_mut().GetByteSize())
     }
 
-    // For uniformity with GetValueAsUnsigned
-    fn get_value_as_unsigned(&mut self) -> Result<u64, Wrapped<bindings::SBError>> {
-        self.get_value_unsigned()
+    fn is_pointer(&mut self) -> SBResult<bool> {
+        Ok(self.get_type()?.is_pointer_type())
     }
 
-    fn get_value_usize(&mut self) -> Result<usize, Wrapped<bindings::SBError>> {
+    // uint32_t GetNumChildren();
+    fn get_num_children(&mut self) -> SBResult<u32> {
+        self.check()?;
+        Ok(self.pin_mut().GetNumChildren())
+    }
+
+    // lldb::SBValue GetChildAtIndex(uint32_t idx);
+    fn child_at_index(&mut self, index: u32) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().GetChildAtIndex(index).wrap())
+    }
+
+    /// All children, struct members or array elements.
+    fn children(&mut self) -> SBResult<Vec<Wrapped<bindings::SBValue>>> {
+        (0..self.get_num_children()?)
+            .map(|i| self.child_at_index(i))
+            .collect()
+    }
+
+    // lldb::SBValue GetChildMemberWithName(const char *name);
+    fn child_by_name(&mut self, name: &str) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        let name = std::ffi::CString::new(name).expect("no null bytes expected");
+        checked(unsafe { self.pin_mut().GetChildMemberWithName(name.as_ptr()) }.wrap())
+    }
+
+    // lldb::SBValue GetValueForExpressionPath(const char *expr_path);
+    /// Resolve a path relative to this value, like `.b[3]->c`.
+    fn value_for_expression_path(&mut self, path: &str) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        let path = std::ffi::CString::new(path).expect("no null bytes expected");
+        checked(unsafe { self.pin_mut().GetValueForExpressionPath(path.as_ptr()) }.wrap())
+    }
+
+    // lldb::SBValue Dereference();
+    fn dereference(&mut self) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().Dereference().wrap())
+    }
+
+    // lldb::SBValue AddressOf();
+    fn address_of(&mut self) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().AddressOf().wrap())
+    }
+
+    // lldb::SBValue Cast(lldb::SBType type);
+    fn cast<T: Type>(&mut self, to: &T) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().Cast(to.as_ref()).wrap())
+    }
+
+    // lldb::SBValue GetDynamicValue(lldb::DynamicValueType use_dynamic);
+    /// The value as its dynamic type, like the derived class behind a base class pointer.
+    fn dynamic_value(
+        &mut self,
+        use_dynamic: bindings::DynamicValueType,
+    ) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().GetDynamicValue(use_dynamic).wrap())
+    }
+
+    // lldb::SBValue GetStaticValue();
+    fn static_value(&mut self) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().GetStaticValue().wrap())
+    }
+
+    // lldb::SBValue GetNonSyntheticValue();
+    /// The raw value, without synthetic children providers like those for STL containers.
+    fn non_synthetic_value(&mut self) -> SBResult<Wrapped<bindings::SBValue>> {
+        self.check()?;
+        checked(self.pin_mut().GetNonSyntheticValue().wrap())
+    }
+
+    // lldb::SBData GetData();
+    /// The raw bytes of the value.
+    fn get_data(&mut self) -> SBResult<Wrapped<bindings::SBData>> {
+        self.check()?;
+        Ok(self.pin_mut().GetData().wrap())
+    }
+
+    // lldb::SBProcess GetProcess();
+    fn process(&mut self) -> Wrapped<bindings::SBProcess> {
+        self.pin_mut().GetProcess().wrap()
+    }
+
+    // lldb::ValueType GetValueType();
+    fn get_value_type(&mut self) -> bindings::ValueType {
+        self.pin_mut().GetValueType()
+    }
+
+    // lldb::SBDeclaration GetDeclaration();
+    /// Where the variable is declared in the source.
+    fn get_declaration(&mut self) -> Wrapped<bindings::SBDeclaration> {
+        self.pin_mut().GetDeclaration().wrap()
+    }
+
+    // bool SetValueFromCString(const char *value_str, lldb::SBError &error);
+    /// Set the value from a string, parsed by LLDB according to the type of the value.
+    fn set_from_str(&mut self, value: &str) -> Result<(), crate::value::WriteError> {
+        use crate::value::WriteError;
+        self.check()?;
+        let value_c = std::ffi::CString::new(value).expect("no null bytes expected");
         let mut e = bindings::SBError::new().wrap();
-        let res = self.pin_mut().GetValueAsUnsigned(e.pin_mut(), 0) as usize;
-        if e.is_success() {
-            return Ok(res);
+        let ok = unsafe {
+            self.pin_mut()
+                .SetValueFromCString1(value_c.as_ptr(), e.pin_mut())
+        };
+        if ok && e.is_success() {
+            return Ok(());
         }
-        Err(e)
+        if self.get_value_type() == bindings::ValueType::eValueTypeConstResult {
+            return Err(WriteError::ReadOnly(self.get_name().unwrap_or_default()));
+        }
+        let msg = if e.is_fail() {
+            e.get_str().to_string()
+        } else {
+            format!("could not set value to {value}")
+        };
+        Err(WriteError::Lldb(msg))
+    }
+
+    /// Set the value from a Rust value, which must have the same size.
+    fn set<T: crate::value::ToValue>(&mut self, value: T) -> Result<(), crate::value::WriteError> {
+        let size = self.byte_size()?;
+        if size != std::mem::size_of::<T>() {
+            return Err(crate::value::WriteError::SizeMismatch {
+                expected: size,
+                found: std::mem::size_of::<T>(),
+            });
+        }
+        self.set_from_str(&value.to_value_string())
+    }
+
+    // bool SetData(lldb::SBData &data, lldb::SBError &error);
+    /// Overwrite the bytes of the value, the data must have the same size.
+    fn set_data<D: Data>(&mut self, data: &mut D) -> Result<(), crate::value::WriteError> {
+        use crate::value::WriteError;
+        let size = self.byte_size()?;
+        if size != data.get_byte_size() {
+            return Err(WriteError::SizeMismatch {
+                expected: size,
+                found: data.get_byte_size(),
+            });
+        }
+        let mut e = bindings::SBError::new().wrap();
+        let ok = self.pin_mut().SetData(data.pin_mut(), e.pin_mut());
+        if ok && e.is_success() {
+            return Ok(());
+        }
+        if self.get_value_type() == bindings::ValueType::eValueTypeConstResult {
+            return Err(WriteError::ReadOnly(self.get_name().unwrap_or_default()));
+        }
+        Err(e.into())
+    }
+
+    /// Snapshot of the value and its children for serialization, up to `depth` levels deep and
+    /// `max_children` children per value.
+    #[cfg(feature = "serde")]
+    fn to_serde(
+        &mut self,
+        depth: usize,
+        max_children: usize,
+    ) -> SBResult<crate::value_tree::ValueNode>
+    where
+        Self: Sized,
+    {
+        let options = crate::value_tree::TreeOptions {
+            depth,
+            max_children,
+            ..Default::default()
+        };
+        crate::value_tree::snapshot(self, &options)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_serde_with(
+        &mut self,
+        options: &crate::value_tree::TreeOptions,
+    ) -> SBResult<crate::value_tree::ValueNode>
+    where
+        Self: Sized,
+    {
+        crate::value_tree::snapshot(self, options)
+    }
+
+    /// Convert into a Rust type.
+    fn get<T: crate::value::FromValue>(&mut self) -> Result<T, crate::value::ValueError>
+    where
+        Self: Sized,
+    {
+        T::from_value(self)
     }
 
     // This super sketchy method casts const to mutable...
@@ -315,6 +2449,144 @@ impl std::fmt::Debug for Wrapped<bindings::SBValue> {
     }
 }
 
+/// Turn values that are invalid or carry an error into an error.
+fn checked(mut value: Wrapped<bindings::SBValue>) -> SBResult<Wrapped<bindings::SBValue>> {
+    value.check()?;
+    Ok(value)
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBData.h
+handle_box_and_uniqueptr!(bindings::SBData);
+pub trait Data: autocxx::PinMut<bindings::SBData> {
+    // size_t GetByteSize();
+    fn get_byte_size(&mut self) -> usize {
+        self.pin_mut().GetByteSize()
+    }
+
+    // float GetFloat(lldb::SBError &error, lldb::offset_t offset);
+    fn get_float(&mut self, offset: u64) -> SBResult<f32> {
+        let mut e = bindings::SBError::new().wrap();
+        let res = self.pin_mut().GetFloat(e.pin_mut(), offset);
+        if e.is_success() {
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // double GetDouble(lldb::SBError &error, lldb::offset_t offset);
+    fn get_double(&mut self, offset: u64) -> SBResult<f64> {
+        let mut e = bindings::SBError::new().wrap();
+        let res = self.pin_mut().GetDouble(e.pin_mut(), offset);
+        if e.is_success() {
+            return Ok(res);
+        }
+        Err(e)
+    }
+
+    // size_t ReadRawData(lldb::SBError &error, lldb::offset_t offset, void *buf, size_t size);
+    /// All bytes held by the data.
+    fn read_raw(&mut self) -> SBResult<Vec<u8>> {
+        let mut res = vec![0u8; self.get_byte_size()];
+        let mut e = bindings::SBError::new().wrap();
+        let len = unsafe {
+            self.pin_mut()
+                .ReadRawData(e.pin_mut(), 0, res.as_mut_ptr() as _, res.len())
+        };
+        if e.is_success() {
+            res.truncate(len);
+            return Ok(res);
+        }
+        Err(e)
+    }
+}
+impl<T> Data for T where T: autocxx::PinMut<bindings::SBData> {}
+
+impl Wrapped<bindings::SBData> {
+    // void SetData(lldb::SBError &error, const void *buf, size_t size, lldb::ByteOrder endian, uint8_t addr_size);
+    /// Create data holding a copy of these bytes.
+    pub fn from_bytes(
+        bytes: &[u8],
+        byte_order: bindings::ByteOrder,
+        address_byte_size: u8,
+    ) -> SBResult<Self> {
+        let mut data = bindings::SBData::new().wrap();
+        let mut e = bindings::SBError::new().wrap();
+        unsafe {
+            data.pin_mut().SetData(
+                e.pin_mut(),
+                bytes.as_ptr() as _,
+                bytes.len(),
+                byte_order,
+                address_byte_size,
+            )
+        };
+        if e.is_success() {
+            return Ok(data);
+        }
+        Err(e)
+    }
+}
+
+// https://github.com/llvm/llvm-project/blob/llvmorg-13.0.1/lldb/include/lldb/API/SBType.h
+handle_box_and_uniqueptr!(bindings::SBType);
+pub trait Type: autocxx::PinMut<bindings::SBType> {
+    fn is_valid(&self) -> bool {
+        self.as_ref().IsValid()
+    }
+
+    // const char *GetName();
+    fn get_name(&mut self) -> String {
+        to_string_opt(self.pin_mut().GetName()).unwrap_or_default()
+    }
+
+    // uint64_t GetByteSize();
+    fn get_byte_size(&mut self) -> u64 {
+        self.pin_mut().GetByteSize()
+    }
+
+    // bool IsPointerType();
+    fn is_pointer_type(&mut self) -> bool {
+        self.pin_mut().IsPointerType()
+    }
+
+    // bool IsReferenceType();
+    fn is_reference_type(&mut self) -> bool {
+        self.pin_mut().IsReferenceType()
+    }
+
+    // bool IsArrayType();
+    fn is_array_type(&mut self) -> bool {
+        self.pin_mut().IsArrayType()
+    }
+
+    // lldb::SBType GetPointerType();
+    fn get_pointer_type(&mut self) -> Wrapped<bindings::SBType> {
+        self.pin_mut().GetPointerType().wrap()
+    }
+
+    // lldb::SBType GetPointeeType();
+    fn get_pointee_type(&mut self) -> Wrapped<bindings::SBType> {
+        self.pin_mut().GetPointeeType().wrap()
+    }
+
+    // lldb::SBType GetArrayElementType();
+    fn get_array_element_type(&mut self) -> Wrapped<bindings::SBType> {
+        self.pin_mut().GetArrayElementType().wrap()
+    }
+
+    // lldb::SBType GetCanonicalType();
+    /// The type with typedefs resolved.
+    fn get_canonical_type(&mut self) -> Wrapped<bindings::SBType> {
+        self.pin_mut().GetCanonicalType().wrap()
+    }
+
+    // lldb::BasicType GetBasicType();
+    fn get_basic_type(&mut self) -> bindings::BasicType {
+        self.pin_mut().GetBasicType()
+    }
+}
+impl<T> Type for T where T: autocxx::PinMut<bindings::SBType> {}
+
 handle_box_and_uniqueptr!(bindings::SBError);
 pub trait Error: autocxx::PinMut<bindings::SBError> {
     /// Returns internal objects Fail() method.
@@ -340,9 +2612,25 @@ pub trait Error: autocxx::PinMut<bindings::SBError> {
     fn get_type(&self) -> bindings::ErrorType {
         self.as_ref().GetType()
     }
+
+    // uint32_t GetError() const;
+    /// The error code, its meaning depends on the error type.
+    fn get_error_code(&self) -> u32 {
+        self.as_ref().GetError()
+    }
 }
 impl<T> Error for T where T: autocxx::PinMut<bindings::SBError> {}
 
+impl Wrapped<bindings::SBError> {
+    /// Create an error with the provided message, for failures detected on our side.
+    pub fn from_message(msg: &str) -> Self {
+        let mut e = bindings::SBError::new().wrap();
+        let msg = std::ffi::CString::new(msg).expect("no null bytes expected");
+        unsafe { e.pin_mut().SetErrorString(msg.as_ptr()) };
+        e
+    }
+}
+
 impl std::fmt::Debug for Wrapped<bindings::SBError> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "Error: {}", self.get_str())
@@ -367,10 +2655,27 @@ pub trait Event: autocxx::PinMut<bindings::SBEvent> {
         bindings::SBProcess::GetStateFromEvent(self.as_ref())
     }
 
-    /// Not sure if this works, doesn't return true for my watchpoint events.
+    /// Only true for watchpoint changed events from the target's broadcaster, hitting a
+    /// watchpoint is a process state change with a watchpoint stop reason on the thread.
     fn is_watchpoint(&self) -> bool {
         bindings::SBWatchpoint::EventIsWatchpointEvent(self.as_ref())
     }
+
+    // uint32_t GetType() const;
+    /// The event bits, their meaning depends on the broadcaster class.
+    fn get_type(&self) -> u32 {
+        self.as_ref().GetType()
+    }
+
+    // const char *GetBroadcasterClass() const;
+    fn get_broadcaster_class(&self) -> String {
+        to_string_opt(self.as_ref().GetBroadcasterClass()).unwrap_or_default()
+    }
+
+    /// Decode this event into plain data, based on its broadcaster class.
+    fn decode(&self) -> crate::events::DecodedEvent {
+        crate::events::decode(self.as_ref())
+    }
 }
 impl<T> Event for T where T: autocxx::PinMut<bindings::SBEvent> {}
 
@@ -456,6 +2761,109 @@ mod test {
         println!("{}", z);
         println!("{:?}", z);
     }
+    #[test]
+    fn test_timeout_seconds() {
+        use std::time::Duration;
+        assert_eq!(timeout_seconds(Duration::from_secs(10)), 10);
+        assert_eq!(timeout_seconds(Duration::from_millis(1500)), 2);
+        assert_eq!(timeout_seconds(Duration::from_millis(1)), 1);
+        assert_eq!(timeout_seconds(Duration::MAX), u32::MAX);
+
+        // A zero timeout returns right away instead of reaching WaitForEvent.
+        let mut listener = Wrapped::<lldb::SBListener>::with_name("lldb_rs.test_timeout");
+        assert!(listener.wait_for_event(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_listener() {
+        let mut listener = Wrapped::<lldb::SBListener>::with_name("test");
+        assert!(listener.is_valid());
+        assert!(listener.peek().is_none());
+        assert_eq!(listener.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_target_expression() {
+        use crate::expression::{ExpressionErrorKind, ExpressionOptions};
+        lldb::SBDebugger::Initialize();
+        let mut dbg = lldb::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+        let options = ExpressionOptions::new();
+        let mut v = target
+            .evaluate_expression("sizeof(int)", &options)
+            .expect("should evaluate");
+        assert_eq!(v.get_value_unsigned().expect("scalar"), 4);
+        let e = target
+            .evaluate_expression("1 +", &options)
+            .expect_err("should not compile");
+        assert_eq!(e.kind, ExpressionErrorKind::Parse);
+        assert!(target
+            .find_global_variables("no_such_global", 1, false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_variables_options() {
+        let options = VariablesOptions::new().locals(false).statics(true);
+        let sb = options.to_sb();
+        assert!(sb.as_ref().GetIncludeArguments());
+        assert!(!sb.as_ref().GetIncludeLocals());
+        assert!(sb.as_ref().GetIncludeStatics());
+        assert!(sb.as_ref().GetInScopeOnly());
+    }
+
+    #[test]
+    fn test_value_write() {
+        let mut value = lldb::SBValue::new().wrap();
+        assert!(value.set_from_str("1").is_err());
+        let mut data = Wrapped::<lldb::SBData>::from_bytes(
+            &[1, 2, 3, 4],
+            lldb::ByteOrder::eByteOrderLittle,
+            8,
+        )
+        .expect("data");
+        assert_eq!(data.get_byte_size(), 4);
+        assert_eq!(data.read_raw().expect("read"), vec![1, 2, 3, 4]);
+        assert!(value.set_data(&mut data).is_err());
+    }
+
+    #[test]
+    fn test_modules() {
+        let spec = Wrapped::<lldb::SBFileSpec>::from_path("/opt/wine/lib/kernel32.dll");
+        assert_eq!(spec.get_filename().as_deref(), Some("kernel32.dll"));
+        assert_eq!(spec.get_path(), "/opt/wine/lib/kernel32.dll");
+        let bare = Wrapped::<lldb::SBFileSpec>::from_path("kernel32.dll");
+        assert_eq!(bare.get_path(), "kernel32.dll");
+
+        lldb::SBDebugger::Initialize();
+        let mut dbg = lldb::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+        assert!(target.modules().is_empty());
+        assert!(target.find_module_by_name("kernel32.dll").is_none());
+        assert!(target.add_module("/no/such/file.dll", None, None).is_err());
+    }
+
+    #[test]
+    fn test_source() {
+        let name = format!("lldb_test_source_{}.c", std::process::id());
+        let path = std::env::temp_dir().join(&name);
+        std::fs::write(&path, "one\ntwo\nthree\nfour\nfive\n").expect("write source");
+
+        lldb::SBDebugger::Initialize();
+        let mut dbg = lldb::SBDebugger::Create().wrap();
+        let mut target = dbg.pin_mut().GetDummyTarget().wrap();
+        let addresses = target.addresses_for_line(&name, 3);
+        let file = Wrapped::<lldb::SBFileSpec>::from_path(&path.to_string_lossy());
+        let lines = target.source_manager().display_lines(&file, 3, 1);
+        std::fs::remove_file(&path).expect("remove source");
+
+        assert!(addresses.is_empty());
+        assert!(lines.contains("two"));
+        assert!(lines.contains("-> "));
+        assert!(lines.contains("four"));
+        assert!(!lines.contains("five"));
+    }
+
     #[test]
     fn test_value() {
         // let mut value = lldb::SBValue::new().wrap();
@@ -467,4 +2875,12 @@ mod test {
         }
         assert_eq!("YES", e.get_str());
     }
+
+    #[test]
+    fn test_value_check() {
+        let mut value = lldb::SBValue::new().wrap();
+        let e = value.check().expect_err("default value is invalid");
+        assert_eq!(e.get_str(), "invalid value");
+        assert!(value.get_name().is_err());
+    }
 }